@@ -0,0 +1,156 @@
+/// Benchmark: table-driven base64 against the original char-based implementation
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use set_1::task_1::{base64_to_bytes, bytes_to_base64, Base64Config};
+
+/// Original implementation, kept here as a baseline.
+mod legacy {
+    const BASE64_CHARS: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    fn base64_to_index(input: char) -> Option<u8> {
+        let char_byte: u8 = input.try_into().ok()?;
+        match input {
+            '+' => Some(62),
+            '/' => Some(63),
+            '0'..='9' => Some(char_byte - 48 + 52),
+            'A'..='Z' => Some(char_byte - 65),
+            'a'..='z' => Some(char_byte - 97 + 26),
+            _ => None,
+        }
+    }
+
+    pub fn bytes_to_base64(src_ref: &[u8]) -> String {
+        let mut result = Vec::new();
+        let mut it = src_ref.iter();
+        loop {
+            let it_0 = it.next();
+            let it_1 = it.next();
+            let it_2 = it.next();
+            if it_0.is_none() {
+                break;
+            }
+            let comp_0 = it_0.map(|ok| ok >> 2);
+            let comp_1 = it_0.map(|ok| 0x3f & ok << 4 | it_1.map(|v| v >> 4).unwrap_or(0));
+            let comp_2 = it_1.map(|ok| 0x3f & ok << 2 | it_2.map(|v| v >> 6).unwrap_or(0));
+            let comp_3 = it_2.map(|ok| 0x3f & ok);
+            #[allow(clippy::useless_vec)]
+            result.extend(
+                vec![comp_0, comp_1, comp_2, comp_3]
+                    .into_iter()
+                    .map(|comp| {
+                        comp.map(|idx| BASE64_CHARS[idx as usize] as char)
+                            .unwrap_or('=')
+                    }),
+            );
+            if it_2.is_none() {
+                break;
+            }
+        }
+        result.into_iter().collect()
+    }
+
+    pub fn base64_to_bytes(input_base64: &str) -> Option<Vec<u8>> {
+        let mut it = input_base64.chars();
+        let mut result = Vec::new();
+        while let Some(it_in) = it.next() {
+            let it_0 = Some(base64_to_index(it_in)?);
+            macro_rules! proc_it {
+                ($($i: ident), *) => {
+                    $(
+                        let $i = it.next()?;
+                        let $i = if $i == '=' {
+                            None
+                        } else {
+                            Some(base64_to_index($i)?)
+                        };
+                    )*
+                }
+            }
+            proc_it!(it_1, it_2, it_3);
+            let comp_0 = it_0.map(|ok| ok << 2 | it_1.map(|v| v >> 4).unwrap_or(0));
+            let mut comp_1 = it_1.map(|ok| ok << 4 | it_2.map(|v| v >> 2).unwrap_or(0));
+            let mut comp_2 = it_2.map(|ok| ok << 6 | it_3.unwrap_or(0));
+            if comp_1 == Some(0) && it_2.is_none() {
+                comp_1 = None
+            }
+            if comp_2 == Some(0) && it_3.is_none() {
+                comp_2 = None
+            }
+            macro_rules! proc_comp {
+                ($($i: ident),*) => {
+                    $(
+                        if let Some(comp) = $i {
+                            result.push(comp);
+                        } else {
+                            break;
+                        }
+                    )*
+                }
+            }
+            proc_comp!(comp_0, comp_1, comp_2);
+        }
+        Some(result)
+    }
+}
+
+fn sample(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7919 % 251) as u8).collect()
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("base64_encode");
+    for len in [64, 4 * 1024, 256 * 1024] {
+        let data = sample(len);
+        assert_eq!(legacy::bytes_to_base64(&data), bytes_to_base64(&data));
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("legacy", len), &data, |b, data| {
+            b.iter(|| legacy::bytes_to_base64(black_box(data)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("bytes_to_base64", len),
+            &data,
+            |b, data| b.iter(|| bytes_to_base64(black_box(data))),
+        );
+        let config = Base64Config::STANDARD;
+        let mut output = vec![0u8; config.encoded_len(len)];
+        group.bench_with_input(
+            BenchmarkId::new("encode_to_slice", len),
+            &data,
+            |b, data| b.iter(|| config.encode_to_slice(black_box(data), &mut output)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("base64_decode");
+    for len in [64, 4 * 1024, 256 * 1024] {
+        let encoded = bytes_to_base64(sample(len));
+        assert_eq!(
+            legacy::base64_to_bytes(&encoded),
+            base64_to_bytes(&encoded).ok()
+        );
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_with_input(BenchmarkId::new("legacy", len), &encoded, |b, encoded| {
+            b.iter(|| legacy::base64_to_bytes(black_box(encoded)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("base64_to_bytes", len),
+            &encoded,
+            |b, encoded| b.iter(|| base64_to_bytes(black_box(encoded))),
+        );
+        let config = Base64Config::STANDARD;
+        let mut output = vec![0u8; Base64Config::decoded_len_estimate(encoded.len())];
+        group.bench_with_input(
+            BenchmarkId::new("decode_to_slice", len),
+            &encoded,
+            |b, encoded| {
+                b.iter(|| config.decode_to_slice(black_box(encoded.as_bytes()), &mut output))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode);
+criterion_main!(benches);
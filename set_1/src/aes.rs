@@ -0,0 +1,406 @@
+/// Pure-Rust AES-128/192/256 block cipher with exposed round functions
+use crate::padding::PaddingError;
+use crate::task_7::AES_BLOCK_SIZE;
+
+pub type Block = [u8; AES_BLOCK_SIZE];
+
+const SBOX: [u8; 256] = build_sbox();
+const INV_SBOX: [u8; 256] = invert_sbox(&SBOX);
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+/// Multiplication by `x` in GF(2^8) modulo `x^8 + x^4 + x^3 + x + 1`.
+const fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+const fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse as `a^254`, zero maps to zero.
+const fn gf_inverse(a: u8) -> u8 {
+    let mut result = 1;
+    let mut exp = 0;
+    while exp < 254 {
+        result = gf_mul(result, a);
+        exp += 1;
+    }
+    result
+}
+
+const fn build_sbox() -> [u8; 256] {
+    let mut sbox = [0; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        let inv = gf_inverse(idx as u8);
+        sbox[idx] = inv
+            ^ inv.rotate_left(1)
+            ^ inv.rotate_left(2)
+            ^ inv.rotate_left(3)
+            ^ inv.rotate_left(4)
+            ^ 0x63;
+        idx += 1;
+    }
+    sbox
+}
+
+const fn invert_sbox(sbox: &[u8; 256]) -> [u8; 256] {
+    let mut inverse = [0; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        inverse[sbox[idx] as usize] = idx as u8;
+        idx += 1;
+    }
+    inverse
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AesError {
+    /// Key is not 16, 24 or 32 bytes long.
+    InvalidKeyLength { len: usize },
+}
+
+impl std::fmt::Display for AesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AesError::InvalidKeyLength { len } => {
+                write!(f, "invalid AES key length {}, expected 16, 24 or 32", len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AesError {}
+
+/// Failure of a decryption which removes padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecryptError {
+    Key(AesError),
+    Padding(PaddingError),
+}
+
+impl std::fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecryptError::Key(err) => write!(f, "{}", err),
+            DecryptError::Padding(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for DecryptError {}
+
+impl From<AesError> for DecryptError {
+    fn from(err: AesError) -> Self {
+        DecryptError::Key(err)
+    }
+}
+
+impl From<PaddingError> for DecryptError {
+    fn from(err: PaddingError) -> Self {
+        DecryptError::Padding(err)
+    }
+}
+
+/// Cipher key, its length selects AES-128, AES-192 or AES-256.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AesKey {
+    Aes128([u8; 16]),
+    Aes192([u8; 24]),
+    Aes256([u8; 32]),
+}
+
+impl AesKey {
+    pub fn new(key: &[u8]) -> Result<Self, AesError> {
+        match key.len() {
+            16 => Ok(AesKey::Aes128(key.try_into().expect("length is checked"))),
+            24 => Ok(AesKey::Aes192(key.try_into().expect("length is checked"))),
+            32 => Ok(AesKey::Aes256(key.try_into().expect("length is checked"))),
+            len => Err(AesError::InvalidKeyLength { len }),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            AesKey::Aes128(key) => key,
+            AesKey::Aes192(key) => key,
+            AesKey::Aes256(key) => key,
+        }
+    }
+
+    /// 10, 12 or 14 depending on key size.
+    pub fn rounds(&self) -> usize {
+        self.as_bytes().len() / 4 + 6
+    }
+}
+
+impl TryFrom<&[u8]> for AesKey {
+    type Error = AesError;
+
+    fn try_from(key: &[u8]) -> Result<Self, AesError> {
+        AesKey::new(key)
+    }
+}
+
+/// Expands the key into `rounds + 1` round keys.
+pub fn expand_key(key: &AesKey) -> Vec<Block> {
+    let key = key.as_bytes();
+    let nk = key.len() / 4;
+    let rounds = nk + 6;
+    let total = 4 * (rounds + 1);
+    let mut words = key
+        .chunks_exact(4)
+        .map(|w| [w[0], w[1], w[2], w[3]])
+        .collect::<Vec<_>>();
+    for idx in nk..total {
+        let mut word = words[idx - 1];
+        if idx % nk == 0 {
+            word.rotate_left(1);
+            word = word.map(|b| SBOX[b as usize]);
+            word[0] ^= RCON[idx / nk - 1];
+        } else if nk > 6 && idx % nk == 4 {
+            word = word.map(|b| SBOX[b as usize]);
+        }
+        let prev = words[idx - nk];
+        words.push([0, 1, 2, 3].map(|i| word[i] ^ prev[i]));
+    }
+    words
+        .chunks_exact(4)
+        .map(|w| {
+            let mut block = [0; AES_BLOCK_SIZE];
+            for (dst, src) in block.chunks_exact_mut(4).zip(w) {
+                dst.copy_from_slice(src);
+            }
+            block
+        })
+        .collect()
+}
+
+pub fn add_round_key(state: &mut Block, round_key: &Block) {
+    state.iter_mut().zip(round_key).for_each(|(s, k)| *s ^= k);
+}
+
+pub fn sub_bytes(state: &mut Block) {
+    state.iter_mut().for_each(|b| *b = SBOX[*b as usize]);
+}
+
+pub fn inv_sub_bytes(state: &mut Block) {
+    state.iter_mut().for_each(|b| *b = INV_SBOX[*b as usize]);
+}
+
+/// State is stored column by column, row `r` is rotated left by `r`.
+pub fn shift_rows(state: &mut Block) {
+    let old = *state;
+    for (idx, b) in state.iter_mut().enumerate() {
+        *b = old[(idx + 4 * (idx % 4)) % AES_BLOCK_SIZE];
+    }
+}
+
+pub fn inv_shift_rows(state: &mut Block) {
+    let old = *state;
+    for (idx, &b) in old.iter().enumerate() {
+        state[(idx + 4 * (idx % 4)) % AES_BLOCK_SIZE] = b;
+    }
+}
+
+pub fn mix_columns(state: &mut Block) {
+    for column in state.chunks_exact_mut(4) {
+        let [a, b, c, d] = [column[0], column[1], column[2], column[3]];
+        let all = a ^ b ^ c ^ d;
+        column[0] ^= all ^ xtime(a ^ b);
+        column[1] ^= all ^ xtime(b ^ c);
+        column[2] ^= all ^ xtime(c ^ d);
+        column[3] ^= all ^ xtime(d ^ a);
+    }
+}
+
+pub fn inv_mix_columns(state: &mut Block) {
+    for column in state.chunks_exact_mut(4) {
+        let old = [column[0], column[1], column[2], column[3]];
+        for (row, b) in column.iter_mut().enumerate() {
+            *b = gf_mul(old[row], 0x0e)
+                ^ gf_mul(old[(row + 1) % 4], 0x0b)
+                ^ gf_mul(old[(row + 2) % 4], 0x0d)
+                ^ gf_mul(old[(row + 3) % 4], 0x09);
+        }
+    }
+}
+
+/// AES with expanded key, works without OpenSSL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Aes {
+    round_keys: Vec<Block>,
+}
+
+impl Aes {
+    /// Key of 16, 24 or 32 bytes selects AES-128, AES-192 or AES-256.
+    pub fn new(key: &[u8]) -> Result<Self, AesError> {
+        AesKey::new(key).map(|key| Self::with_key(&key))
+    }
+
+    pub fn with_key(key: &AesKey) -> Self {
+        Self {
+            round_keys: expand_key(key),
+        }
+    }
+
+    /// 10, 12 or 14 depending on key size.
+    pub fn rounds(&self) -> usize {
+        self.round_keys.len() - 1
+    }
+
+    /// Round keys in order of encryption, the first one is the cipher key prefix.
+    pub fn round_keys(&self) -> &[Block] {
+        &self.round_keys
+    }
+
+    pub fn encrypt_block(&self, block: &mut Block) {
+        self.encrypt_rounds(block, self.rounds());
+    }
+
+    pub fn decrypt_block(&self, block: &mut Block) {
+        self.decrypt_rounds(block, self.rounds());
+    }
+
+    /// Encrypts with the first `rounds` round keys, the last performed round
+    /// skips MixColumns like the final round of the full cipher.
+    pub fn encrypt_rounds(&self, block: &mut Block, rounds: usize) {
+        assert!(
+            (1..=self.rounds()).contains(&rounds),
+            "Rounds must be in 1..={}",
+            self.rounds()
+        );
+        add_round_key(block, &self.round_keys[0]);
+        for round in 1..=rounds {
+            sub_bytes(block);
+            shift_rows(block);
+            if round != rounds {
+                mix_columns(block);
+            }
+            add_round_key(block, &self.round_keys[round]);
+        }
+    }
+
+    /// Inverse of `encrypt_rounds` with the same number of rounds.
+    pub fn decrypt_rounds(&self, block: &mut Block, rounds: usize) {
+        assert!(
+            (1..=self.rounds()).contains(&rounds),
+            "Rounds must be in 1..={}",
+            self.rounds()
+        );
+        for round in (1..=rounds).rev() {
+            add_round_key(block, &self.round_keys[round]);
+            if round != rounds {
+                inv_mix_columns(block);
+            }
+            inv_shift_rows(block);
+            inv_sub_bytes(block);
+        }
+        add_round_key(block, &self.round_keys[0]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(hex_str: &str) -> Block {
+        hex::decode(hex_str).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn fips_197_vectors_should_pass() {
+        let plaintext = block("00112233445566778899aabbccddeeff");
+        let key = hex::decode("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f")
+            .unwrap();
+        // FIPS-197, appendix C
+        for (key_len, expected) in [
+            (16, "69c4e0d86a7b0430d8cdb78070b4c55a"),
+            (24, "dda97ca4864cdfe06eaf70a0ec0d7191"),
+            (32, "8ea2b7ca516745bfeafc49904b496089"),
+        ] {
+            let aes = Aes::new(&key[..key_len]).unwrap();
+            assert_eq!(aes.rounds(), key_len / 4 + 6);
+            let mut state = plaintext;
+            aes.encrypt_block(&mut state);
+            assert_eq!(state, block(expected));
+            aes.decrypt_block(&mut state);
+            assert_eq!(state, plaintext);
+        }
+        assert_eq!(
+            Aes::new(b"short"),
+            Err(AesError::InvalidKeyLength { len: 5 })
+        );
+    }
+
+    #[test]
+    fn key_expansion_should_pass() {
+        // FIPS-197, appendix A
+        let aes = Aes::new(&hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap()).unwrap();
+        assert_eq!(aes.round_keys().len(), 11);
+        assert_eq!(
+            aes.round_keys()[1],
+            block("a0fafe1788542cb123a339392a6c7605")
+        );
+        assert_eq!(
+            aes.round_keys()[10],
+            block("d014f9a8c9ee2589e13f0cc8b6630ca6")
+        );
+        let key = hex::decode("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4")
+            .unwrap();
+        let aes = Aes::new(&key).unwrap();
+        assert_eq!(
+            aes.round_keys()[14],
+            block("fe4890d1e6188d0b046df344706c631e")
+        );
+
+        let mut state = block("3243f6a8885a308d313198a2e0370734");
+        aes.encrypt_rounds(&mut state, 3);
+        assert_ne!(state, block("3243f6a8885a308d313198a2e0370734"));
+        aes.decrypt_rounds(&mut state, 3);
+        assert_eq!(state, block("3243f6a8885a308d313198a2e0370734"));
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn openssl_differential_should_pass() {
+        use openssl::symm::{encrypt, Cipher};
+
+        let mut seed = 0x2545f491u32;
+        let mut next_bytes = |count: usize| {
+            (0..count)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    seed as u8
+                })
+                .collect::<Vec<_>>()
+        };
+        for (key_len, cipher) in [
+            (16, Cipher::aes_128_ecb()),
+            (24, Cipher::aes_192_ecb()),
+            (32, Cipher::aes_256_ecb()),
+        ] {
+            for _ in 0..32 {
+                let key = next_bytes(key_len);
+                let plaintext: Block = next_bytes(AES_BLOCK_SIZE).try_into().unwrap();
+                let expected = encrypt(cipher, &key, None, &plaintext).unwrap();
+                let aes = Aes::new(&key).unwrap();
+                let mut state = plaintext;
+                aes.encrypt_block(&mut state);
+                assert_eq!(state[..], expected[..AES_BLOCK_SIZE]);
+                aes.decrypt_block(&mut state);
+                assert_eq!(state, plaintext);
+            }
+        }
+    }
+}
@@ -0,0 +1,316 @@
+/// Streaming base64 adapters over `std::io::Read` and `std::io::Write`
+use crate::task_1::{Base64Config, Base64Decoder, Base64Error, Base64LineWrap};
+use std::io::{self, Read, Write};
+
+/// Size of the raw read buffer, also bounds the amount of text decoded per refill.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+impl From<Base64Error> for io::Error {
+    fn from(err: Base64Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Decodes base64 text read from `inner` on the fly, line breaks are skipped.
+///
+/// Error offsets are counted from the start of the stream.
+pub struct Base64Reader<R: Read> {
+    inner: R,
+    decoder: Base64Decoder,
+    raw: Vec<u8>,
+    decoded: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl<R: Read> Base64Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_config(inner, Base64Config::STANDARD)
+    }
+
+    pub fn with_config(inner: R, config: Base64Config) -> Self {
+        Self {
+            inner,
+            decoder: Base64Decoder::new(config).skip_line_breaks(true),
+            raw: vec![0u8; CHUNK_SIZE],
+            decoded: Vec::new(),
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads next chunk of text and decodes all complete groups from it.
+    fn refill(&mut self) -> io::Result<()> {
+        self.decoded.clear();
+        self.pos = 0;
+        while self.decoded.is_empty() && !self.eof {
+            let count = match self.inner.read(&mut self.raw) {
+                Ok(count) => count,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if count == 0 {
+                self.eof = true;
+                self.decoder.finish(&mut self.decoded)?;
+            } else {
+                self.decoder.feed(&self.raw[..count], &mut self.decoded)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Base64Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos == self.decoded.len() {
+            self.refill()?;
+        }
+        let count = buf.len().min(self.decoded.len() - self.pos);
+        buf[..count].copy_from_slice(&self.decoded[self.pos..self.pos + count]);
+        self.pos += count;
+        Ok(count)
+    }
+}
+
+/// Encodes bytes written to it as base64 text and passes it to `inner`.
+///
+/// Trailing bytes which don't form a full group are held back until [`Base64Writer::finish`]
+/// is called, dropping the writer finishes it as well but ignores errors.
+pub struct Base64Writer<W: Write> {
+    inner: Option<W>,
+    /// Config used for encoding separate chunks, wrapping is done by the writer itself.
+    config: Base64Config,
+    line_wrap: Option<Base64LineWrap>,
+    /// Input bytes which don't form a full 3-byte group yet.
+    pending: Vec<u8>,
+    /// Chars written to the current line.
+    column: usize,
+}
+
+impl<W: Write> Base64Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_config(inner, Base64Config::STANDARD)
+    }
+
+    pub fn with_config(inner: W, config: Base64Config) -> Self {
+        Self {
+            inner: Some(inner),
+            config: Base64Config {
+                line_wrap: None,
+                ..config
+            },
+            line_wrap: config.line_wrap.filter(|wrap| wrap.width > 0),
+            pending: Vec::with_capacity(3),
+            column: 0,
+        }
+    }
+
+    /// Writes remaining bytes along with padding, returns underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_tail()?;
+        Ok(self.inner.take().expect("writer is present until finished"))
+    }
+
+    fn write_tail(&mut self) -> io::Result<()> {
+        if self.inner.is_some() {
+            if !self.pending.is_empty() {
+                let encoded = self.config.encode(&self.pending);
+                self.emit(&encoded)?;
+                self.pending.clear();
+            }
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, encoded: &str) -> io::Result<()> {
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("writer is present until finished");
+        let wrap = match self.line_wrap {
+            Some(wrap) => wrap,
+            None => return inner.write_all(encoded.as_bytes()),
+        };
+        let mut text = encoded.as_bytes();
+        while !text.is_empty() {
+            if self.column == wrap.width {
+                inner.write_all(wrap.line_ending.as_bytes())?;
+                self.column = 0;
+            }
+            let count = (wrap.width - self.column).min(text.len());
+            inner.write_all(&text[..count])?;
+            self.column += count;
+            text = &text[count..];
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Base64Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut input = buf;
+        if !self.pending.is_empty() {
+            let missing = (3 - self.pending.len()).min(input.len());
+            self.pending.extend_from_slice(&input[..missing]);
+            input = &input[missing..];
+            if self.pending.len() < 3 {
+                return Ok(buf.len());
+            }
+            let encoded = self.config.encode(&self.pending);
+            self.emit(&encoded)?;
+            self.pending.clear();
+        }
+        let aligned = input.len() - input.len() % 3;
+        for chunk in input[..aligned].chunks(CHUNK_SIZE / 4 * 3) {
+            let encoded = self.config.encode(chunk);
+            self.emit(&encoded)?;
+        }
+        self.pending.extend_from_slice(&input[aligned..]);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for Base64Writer<W> {
+    fn drop(&mut self) {
+        let _ = self.write_tail();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_1::{base64_to_bytes, bytes_to_base64};
+    use std::fs::File;
+
+    /// Hands out at most `step` bytes per call to exercise chunk boundaries.
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let count = self.1.min(buf.len()).min(self.0.len());
+            buf[..count].copy_from_slice(&self.0[..count]);
+            self.0 = &self.0[count..];
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn base64_reader_should_pass() {
+        let text =
+            b"SSdtIGtpbGxpbmcgeW91ciBicmFp\r\nbiBsaWtlIGEgcG9pc29ub3Vz\nIG11c2hyb29t\nZm9vYg==\n";
+        for step in 1..=7 {
+            let mut output = Vec::new();
+            Base64Reader::new(Trickle(text, step))
+                .read_to_end(&mut output)
+                .unwrap();
+            assert_eq!(
+                output,
+                b"I'm killing your brain like a poisonous mushroomfoob"
+            );
+        }
+
+        let mut output = Vec::new();
+        let result = Base64Reader::new(&b"Zm9vY"[..]).read_to_end(&mut output);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let result = Base64Reader::new(&b"Zm9v\nY!==\n"[..]).read_to_end(&mut output);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let result = Base64Reader::new("Zm9v\nYé==\n".as_bytes()).read_to_end(&mut output);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn base64_writer_should_pass() {
+        let data = b"I'm killing your brain like a poisonous mushroom!";
+        for step in 1..=7 {
+            let mut writer = Base64Writer::new(Vec::new());
+            for chunk in data.chunks(step) {
+                writer.write_all(chunk).unwrap();
+            }
+            let output = writer.finish().unwrap();
+            assert_eq!(output, bytes_to_base64(data).into_bytes());
+        }
+
+        let mut output = Vec::new();
+        {
+            let mut writer = Base64Writer::new(&mut output);
+            writer.write_all(b"foob").unwrap();
+        }
+        assert_eq!(output, b"Zm9vYg==");
+    }
+
+    #[test]
+    fn base64_reader_file_should_pass() {
+        let file = File::open("res/task7.txt").expect("Failed to open file.");
+        let mut cipher = Vec::new();
+        Base64Reader::new(file)
+            .read_to_end(&mut cipher)
+            .expect("Failed to decode base64.");
+        assert_eq!(cipher.len(), 2880);
+
+        let mut encoded = Base64Writer::new(Vec::new());
+        encoded.write_all(&cipher).unwrap();
+        let encoded = String::from_utf8(encoded.finish().unwrap()).unwrap();
+        assert_eq!(base64_to_bytes(&encoded).unwrap(), cipher);
+    }
+
+    #[test]
+    fn base64_config_streaming_should_pass() {
+        let data = (0..=255u8).collect::<Vec<_>>();
+        for config in [Base64Config::URL_SAFE_NO_PAD, Base64Config::MIME] {
+            for step in [1, 2, 5, 64] {
+                let mut writer = Base64Writer::with_config(Vec::new(), config);
+                for chunk in data.chunks(step) {
+                    writer.write_all(chunk).unwrap();
+                }
+                let encoded = writer.finish().unwrap();
+                assert_eq!(encoded, config.encode(&data).into_bytes());
+
+                let mut decoded = Vec::new();
+                Base64Reader::with_config(Trickle(&encoded, step), config)
+                    .read_to_end(&mut decoded)
+                    .unwrap();
+                assert_eq!(decoded, data);
+            }
+        }
+    }
+
+    #[test]
+    fn base64_reader_strict_should_pass() {
+        let strict = Base64Config {
+            strict: true,
+            ..Base64Config::STANDARD
+        };
+        for step in 1..=5 {
+            let mut output = Vec::new();
+            let err = Base64Reader::with_config(Trickle(b"Zm9v\nZg==\nZm9v\n", step), strict)
+                .read_to_end(&mut output)
+                .unwrap_err();
+            let err = err
+                .into_inner()
+                .and_then(|e| e.downcast::<Base64Error>().ok())
+                .unwrap();
+            assert_eq!(
+                *err,
+                Base64Error::TrailingData {
+                    offset: 10,
+                    ch: 'Z'
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,491 @@
+/// Common interface for block ciphers and generic modes of operation built on it
+use crate::aes::{Aes, Block};
+use crate::task_7::AES_BLOCK_SIZE;
+
+pub trait BlockCipher {
+    fn block_size(&self) -> usize;
+    /// `block` must be exactly `block_size` long.
+    fn encrypt_block(&self, block: &mut [u8]);
+    /// `block` must be exactly `block_size` long.
+    fn decrypt_block(&self, block: &mut [u8]);
+}
+
+impl BlockCipher for Aes {
+    fn block_size(&self) -> usize {
+        AES_BLOCK_SIZE
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let block: &mut Block = block.try_into().expect("Input must be of block size");
+        Aes::encrypt_block(self, block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let block: &mut Block = block.try_into().expect("Input must be of block size");
+        Aes::decrypt_block(self, block);
+    }
+}
+
+impl<C: BlockCipher + ?Sized> BlockCipher for &C {
+    fn block_size(&self) -> usize {
+        (**self).block_size()
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        (**self).encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        (**self).decrypt_block(block)
+    }
+}
+
+fn assert_aligned<C: BlockCipher + ?Sized>(cipher: &C, data: &[u8]) {
+    assert_eq!(
+        data.len() % cipher.block_size(),
+        0,
+        "Input size is not aligned to block size"
+    );
+}
+
+pub(crate) fn xor_in_place(target: &mut [u8], other: &[u8]) {
+    target.iter_mut().zip(other).for_each(|(t, o)| *t ^= o);
+}
+
+/// Electronic CodeBook, input must be aligned to block size, no padding is applied.
+pub fn encrypt_ecb<C: BlockCipher + ?Sized>(cipher: &C, plaintext: &[u8]) -> Vec<u8> {
+    assert_aligned(cipher, plaintext);
+    let mut output = plaintext.to_vec();
+    output
+        .chunks_exact_mut(cipher.block_size())
+        .for_each(|block| cipher.encrypt_block(block));
+    output
+}
+
+pub fn decrypt_ecb<C: BlockCipher + ?Sized>(cipher: &C, ciphertext: &[u8]) -> Vec<u8> {
+    assert_aligned(cipher, ciphertext);
+    let mut output = ciphertext.to_vec();
+    output
+        .chunks_exact_mut(cipher.block_size())
+        .for_each(|block| cipher.decrypt_block(block));
+    output
+}
+
+/// Cipher Block Chaining, input must be aligned to block size, no padding is applied.
+pub fn encrypt_cbc<C: BlockCipher + ?Sized>(cipher: &C, plaintext: &[u8], iv: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size(), "IV must be of block size");
+    assert_aligned(cipher, plaintext);
+    let mut output = plaintext.to_vec();
+    let mut prev = iv.to_vec();
+    for block in output.chunks_exact_mut(cipher.block_size()) {
+        xor_in_place(block, &prev);
+        cipher.encrypt_block(block);
+        prev.copy_from_slice(block);
+    }
+    output
+}
+
+pub fn decrypt_cbc<C: BlockCipher + ?Sized>(cipher: &C, ciphertext: &[u8], iv: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size(), "IV must be of block size");
+    assert_aligned(cipher, ciphertext);
+    let mut output = ciphertext.to_vec();
+    let prev_blocks = std::iter::once(iv).chain(ciphertext.chunks_exact(cipher.block_size()));
+    for (block, prev) in output
+        .chunks_exact_mut(cipher.block_size())
+        .zip(prev_blocks)
+    {
+        cipher.decrypt_block(block);
+        xor_in_place(block, prev);
+    }
+    output
+}
+
+/// Cipher FeedBack with 8-bit segments, input of any length.
+pub fn encrypt_cfb8<C: BlockCipher + ?Sized>(cipher: &C, plaintext: &[u8], iv: &[u8]) -> Vec<u8> {
+    cfb8(cipher, plaintext, iv, true)
+}
+
+pub fn decrypt_cfb8<C: BlockCipher + ?Sized>(cipher: &C, ciphertext: &[u8], iv: &[u8]) -> Vec<u8> {
+    cfb8(cipher, ciphertext, iv, false)
+}
+
+/// Shift register is fed with ciphertext bytes.
+fn cfb8<C: BlockCipher + ?Sized>(cipher: &C, input: &[u8], iv: &[u8], encrypt: bool) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size(), "IV must be of block size");
+    let mut register = iv.to_vec();
+    let mut keystream = vec![0; register.len()];
+    input
+        .iter()
+        .map(|&b| {
+            keystream.copy_from_slice(&register);
+            cipher.encrypt_block(&mut keystream);
+            let output = b ^ keystream[0];
+            register.rotate_left(1);
+            *register.last_mut().expect("block is not empty") = if encrypt { output } else { b };
+            output
+        })
+        .collect()
+}
+
+/// Cipher FeedBack with full block segments (CFB-128 for AES), the last segment may be partial.
+pub fn encrypt_cfb<C: BlockCipher + ?Sized>(cipher: &C, plaintext: &[u8], iv: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size(), "IV must be of block size");
+    let mut output = plaintext.to_vec();
+    let mut register = iv.to_vec();
+    for segment in output.chunks_mut(cipher.block_size()) {
+        cipher.encrypt_block(&mut register);
+        xor_in_place(segment, &register);
+        register[..segment.len()].copy_from_slice(segment);
+    }
+    output
+}
+
+pub fn decrypt_cfb<C: BlockCipher + ?Sized>(cipher: &C, ciphertext: &[u8], iv: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size(), "IV must be of block size");
+    let mut output = ciphertext.to_vec();
+    let mut register = iv.to_vec();
+    for (segment, cipher_segment) in output
+        .chunks_mut(cipher.block_size())
+        .zip(ciphertext.chunks(cipher.block_size()))
+    {
+        cipher.encrypt_block(&mut register);
+        xor_in_place(segment, &register);
+        register[..segment.len()].copy_from_slice(cipher_segment);
+    }
+    output
+}
+
+/// Output FeedBack, keystream doesn't depend on data, input of any length.
+pub fn encrypt_ofb<C: BlockCipher + ?Sized>(cipher: &C, plaintext: &[u8], iv: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size(), "IV must be of block size");
+    let mut output = plaintext.to_vec();
+    let mut register = iv.to_vec();
+    for segment in output.chunks_mut(cipher.block_size()) {
+        cipher.encrypt_block(&mut register);
+        xor_in_place(segment, &register);
+    }
+    output
+}
+
+pub fn decrypt_ofb<C: BlockCipher + ?Sized>(cipher: &C, ciphertext: &[u8], iv: &[u8]) -> Vec<u8> {
+    encrypt_ofb(cipher, ciphertext, iv)
+}
+
+/// Propagating CBC, both plaintext and ciphertext of a block are chained into the next one.
+/// Input must be aligned to block size, no padding is applied.
+pub fn encrypt_pcbc<C: BlockCipher + ?Sized>(cipher: &C, plaintext: &[u8], iv: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size(), "IV must be of block size");
+    assert_aligned(cipher, plaintext);
+    let mut output = plaintext.to_vec();
+    let mut chain = iv.to_vec();
+    for (block, plain) in output
+        .chunks_exact_mut(cipher.block_size())
+        .zip(plaintext.chunks_exact(cipher.block_size()))
+    {
+        xor_in_place(block, &chain);
+        cipher.encrypt_block(block);
+        chain.copy_from_slice(block);
+        xor_in_place(&mut chain, plain);
+    }
+    output
+}
+
+pub fn decrypt_pcbc<C: BlockCipher + ?Sized>(cipher: &C, ciphertext: &[u8], iv: &[u8]) -> Vec<u8> {
+    assert_eq!(iv.len(), cipher.block_size(), "IV must be of block size");
+    assert_aligned(cipher, ciphertext);
+    let mut output = ciphertext.to_vec();
+    let mut chain = iv.to_vec();
+    for (block, encrypted) in output
+        .chunks_exact_mut(cipher.block_size())
+        .zip(ciphertext.chunks_exact(cipher.block_size()))
+    {
+        cipher.decrypt_block(block);
+        xor_in_place(block, &chain);
+        chain.copy_from_slice(block);
+        xor_in_place(&mut chain, encrypted);
+    }
+    output
+}
+
+/// Placement of the two last blocks in CBC with ciphertext stealing, NIST SP 800-38A addendum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CiphertextStealing {
+    /// Truncated penultimate block goes first.
+    Cs1,
+    /// Blocks are swapped only when the last one is partial.
+    Cs2,
+    /// Blocks are always swapped, used by Kerberos.
+    Cs3,
+}
+
+impl CiphertextStealing {
+    fn swaps(&self, partial: bool) -> bool {
+        match self {
+            CiphertextStealing::Cs1 => false,
+            CiphertextStealing::Cs2 => partial,
+            CiphertextStealing::Cs3 => true,
+        }
+    }
+}
+
+/// Returns number of blocks and length of the last, possibly partial, one.
+fn stealing_layout<C: BlockCipher + ?Sized>(cipher: &C, len: usize) -> (usize, usize) {
+    let block_size = cipher.block_size();
+    assert!(
+        len >= block_size,
+        "Input must be at least one block long for ciphertext stealing"
+    );
+    let blocks = len.div_ceil(block_size);
+    (blocks, len - (blocks - 1) * block_size)
+}
+
+/// CBC without padding, ciphertext is as long as plaintext, which must be
+/// at least one block long.
+pub fn encrypt_cbc_cts<C: BlockCipher + ?Sized>(
+    cipher: &C,
+    plaintext: &[u8],
+    iv: &[u8],
+    variant: CiphertextStealing,
+) -> Vec<u8> {
+    let block_size = cipher.block_size();
+    let (blocks, last_len) = stealing_layout(cipher, plaintext.len());
+    let mut padded = plaintext.to_vec();
+    padded.resize(blocks * block_size, 0);
+    let mut output = encrypt_cbc(cipher, &padded, iv);
+    if blocks == 1 {
+        return output;
+    }
+    let tail = output.split_off((blocks - 2) * block_size);
+    let (penultimate, last) = tail.split_at(block_size);
+    if variant.swaps(last_len < block_size) {
+        output.extend_from_slice(last);
+        output.extend_from_slice(&penultimate[..last_len]);
+    } else {
+        output.extend_from_slice(&penultimate[..last_len]);
+        output.extend_from_slice(last);
+    }
+    output
+}
+
+pub fn decrypt_cbc_cts<C: BlockCipher + ?Sized>(
+    cipher: &C,
+    ciphertext: &[u8],
+    iv: &[u8],
+    variant: CiphertextStealing,
+) -> Vec<u8> {
+    let block_size = cipher.block_size();
+    let (blocks, last_len) = stealing_layout(cipher, ciphertext.len());
+    if blocks == 1 {
+        return decrypt_cbc(cipher, ciphertext, iv);
+    }
+    let head_len = (blocks - 2) * block_size;
+    let tail = &ciphertext[head_len..];
+    let (stolen, last) = if variant.swaps(last_len < block_size) {
+        let (last, stolen) = tail.split_at(block_size);
+        (stolen, last)
+    } else {
+        tail.split_at(last_len)
+    };
+    // last block decrypts into the partial plaintext XOR-ed with the full penultimate block
+    let mut decrypted_last = last.to_vec();
+    cipher.decrypt_block(&mut decrypted_last);
+    let mut chained = ciphertext[..head_len].to_vec();
+    chained.extend_from_slice(stolen);
+    chained.extend_from_slice(&decrypted_last[last_len..]);
+    let mut output = decrypt_cbc(cipher, &chained, iv);
+    output.extend(
+        decrypted_last[..last_len]
+            .iter()
+            .zip(stolen)
+            .map(|(d, c)| d ^ c),
+    );
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Toy 4-byte cipher: XOR with key, then rotate bytes.
+    struct Toy(u8);
+
+    impl BlockCipher for Toy {
+        fn block_size(&self) -> usize {
+            4
+        }
+
+        fn encrypt_block(&self, block: &mut [u8]) {
+            block.iter_mut().for_each(|b| *b ^= self.0);
+            block.rotate_left(1);
+        }
+
+        fn decrypt_block(&self, block: &mut [u8]) {
+            block.rotate_right(1);
+            block.iter_mut().for_each(|b| *b ^= self.0);
+        }
+    }
+
+    #[test]
+    fn aes_modes_should_pass() {
+        // NIST SP 800-38A, F.1.1 and F.2.1
+        let aes = Aes::new(&hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap()).unwrap();
+        let plaintext =
+            hex::decode("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51")
+                .unwrap();
+        let ecb = encrypt_ecb(&aes, &plaintext);
+        assert_eq!(
+            hex::encode(&ecb),
+            "3ad77bb40d7a3660a89ecaf32466ef97f5d3d58503b9699de785895a96fdbaaf"
+        );
+        assert_eq!(decrypt_ecb(&aes, &ecb), plaintext);
+        let iv = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let cbc = encrypt_cbc(&aes, &plaintext, &iv);
+        assert_eq!(
+            hex::encode(&cbc),
+            "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2"
+        );
+        assert_eq!(decrypt_cbc(&aes, &cbc, &iv), plaintext);
+    }
+
+    #[test]
+    fn generic_modes_should_pass() {
+        let toy = Toy(0x5a);
+        let plaintext = b"ABCDABCDEFGH";
+        let ecb = encrypt_ecb(&toy, plaintext);
+        assert_eq!(ecb[..4], ecb[4..8]);
+        assert_eq!(decrypt_ecb(&toy, &ecb), plaintext);
+        let cbc = encrypt_cbc(&toy, plaintext, b"\x01\x02\x03\x04");
+        assert_ne!(cbc[..4], cbc[4..8]);
+        assert_eq!(decrypt_cbc(&toy, &cbc, b"\x01\x02\x03\x04"), plaintext);
+        let dynamic: &dyn BlockCipher = &toy;
+        assert_eq!(encrypt_ecb(dynamic, plaintext), ecb);
+    }
+
+    #[test]
+    fn feedback_modes_should_pass() {
+        // NIST SP 800-38A, F.3.7, F.3.13 and F.4.1
+        let aes = Aes::new(&hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap()).unwrap();
+        let iv = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let plaintext = hex::decode(
+            "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+             30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710",
+        )
+        .unwrap();
+        let cfb8 = encrypt_cfb8(&aes, &plaintext[..18], &iv);
+        assert_eq!(hex::encode(&cfb8), "3b79424c9c0dd436bace9e0ed4586a4f32b9");
+        assert_eq!(decrypt_cfb8(&aes, &cfb8, &iv), &plaintext[..18]);
+        let cfb = encrypt_cfb(&aes, &plaintext, &iv);
+        assert_eq!(
+            hex::encode(&cfb),
+            "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+             26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6"
+        );
+        assert_eq!(decrypt_cfb(&aes, &cfb, &iv), plaintext);
+        assert_eq!(encrypt_cfb(&aes, &plaintext[..20], &iv), &cfb[..20]);
+        assert_eq!(decrypt_cfb(&aes, &cfb[..20], &iv), &plaintext[..20]);
+        let ofb = encrypt_ofb(&aes, &plaintext, &iv);
+        assert_eq!(
+            hex::encode(&ofb),
+            "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825\
+             9740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e"
+        );
+        assert_eq!(decrypt_ofb(&aes, &ofb[..41], &iv), &plaintext[..41]);
+        let pcbc = encrypt_pcbc(&aes, &plaintext, &iv);
+        assert_eq!(pcbc[..16], encrypt_cbc(&aes, &plaintext, &iv)[..16]);
+        assert_ne!(pcbc[16..], encrypt_cbc(&aes, &plaintext, &iv)[16..]);
+        assert_eq!(decrypt_pcbc(&aes, &pcbc, &iv), plaintext);
+    }
+
+    /// Positions of bytes which differ.
+    fn diff(a: &[u8], b: &[u8]) -> Vec<usize> {
+        (0..a.len()).filter(|&idx| a[idx] != b[idx]).collect()
+    }
+
+    #[test]
+    fn error_propagation_should_pass() {
+        let aes = Aes::new(b"YELLOW SUBMARINE").unwrap();
+        let iv = [0x42u8; AES_BLOCK_SIZE];
+        let plaintext = [0u8; 4 * AES_BLOCK_SIZE];
+        let flip = |mut data: Vec<u8>, idx: usize| {
+            data[idx] ^= 1;
+            data
+        };
+        // CBC: flipped bit garbles its block and flips the same bit in the next one
+        let cbc = decrypt_cbc(&aes, &flip(encrypt_cbc(&aes, &plaintext, &iv), 5), &iv);
+        assert!(diff(&cbc, &plaintext)
+            .iter()
+            .all(|&idx| idx < 16 || idx == 21));
+        assert_eq!(cbc[21], 1);
+        // CFB: flips the same bit and garbles the next segment
+        let cfb = decrypt_cfb(&aes, &flip(encrypt_cfb(&aes, &plaintext, &iv), 5), &iv);
+        assert_eq!(cfb[5], 1);
+        assert!(diff(&cfb, &plaintext)
+            .iter()
+            .all(|&idx| idx == 5 || (16..32).contains(&idx)));
+        // CFB-8: garbles bytes while the bad byte is in the shift register
+        let cfb8 = decrypt_cfb8(&aes, &flip(encrypt_cfb8(&aes, &plaintext, &iv), 5), &iv);
+        assert_eq!(cfb8[5], 1);
+        assert!(diff(&cfb8, &plaintext)
+            .iter()
+            .all(|&idx| (5..=21).contains(&idx)));
+        // OFB: only the flipped bit, fully malleable
+        let ofb = decrypt_ofb(&aes, &flip(encrypt_ofb(&aes, &plaintext, &iv), 5), &iv);
+        assert_eq!(diff(&ofb, &plaintext), vec![5]);
+        // PCBC: error propagates to the end, but swapped blocks only affect themselves
+        let pcbc = encrypt_pcbc(&aes, &plaintext, &iv);
+        let garbled = decrypt_pcbc(&aes, &flip(pcbc.clone(), 5), &iv);
+        assert!((0..4).all(|block| garbled[block * 16..][..16] != plaintext[..16]));
+        let mut swapped = pcbc;
+        let (first, second) = swapped.split_at_mut(16);
+        first.swap_with_slice(&mut second[..16]);
+        let decrypted = decrypt_pcbc(&aes, &swapped, &iv);
+        assert_ne!(decrypted[..32], plaintext[..32]);
+        assert_eq!(decrypted[32..], plaintext[32..]);
+    }
+
+    #[test]
+    fn ciphertext_stealing_should_pass() {
+        // RFC 3962, appendix B, Kerberos uses CS3
+        let aes = Aes::new(b"chicken teriyaki").unwrap();
+        let iv = [0u8; AES_BLOCK_SIZE];
+        let plaintext = b"I would like the General Gau's Chicken, please, and wonton soup.";
+        for (len, expected) in [
+            (17, "c6353568f2bf8cb4d8a580362da7ff7f97"),
+            (
+                31,
+                "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+            ),
+            (
+                32,
+                "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584",
+            ),
+        ] {
+            let cipher = encrypt_cbc_cts(&aes, &plaintext[..len], &iv, CiphertextStealing::Cs3);
+            assert_eq!(hex::encode(&cipher), expected);
+            assert_eq!(
+                decrypt_cbc_cts(&aes, &cipher, &iv, CiphertextStealing::Cs3),
+                &plaintext[..len]
+            );
+        }
+
+        for len in [16, 17, 31, 32, 33, 48, 63] {
+            let cbc = encrypt_cbc(&aes, &plaintext[..len / 16 * 16], &iv);
+            for variant in [
+                CiphertextStealing::Cs1,
+                CiphertextStealing::Cs2,
+                CiphertextStealing::Cs3,
+            ] {
+                let cipher = encrypt_cbc_cts(&aes, &plaintext[..len], &iv, variant);
+                assert_eq!(cipher.len(), len);
+                assert_eq!(
+                    decrypt_cbc_cts(&aes, &cipher, &iv, variant),
+                    &plaintext[..len]
+                );
+                // aligned input is plain CBC unless CS3 swaps the blocks
+                if len % 16 == 0 && (variant != CiphertextStealing::Cs3 || len == 16) {
+                    assert_eq!(cipher, cbc);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+/// Streaming encryption and decryption adapters over `std::io::Read` and `std::io::Write`
+use crate::crypter::Incremental;
+use std::io::{self, Read, Write};
+
+/// Size of the raw read buffer, memory use doesn't depend on the stream length.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Passes data read from `inner` through an `Encryptor` or `Decryptor`.
+///
+/// Padding errors surface as `InvalidData` once the end of `inner` is reached.
+pub struct CipherReader<R: Read, T: Incremental> {
+    inner: R,
+    crypter: Option<T>,
+    raw: Vec<u8>,
+    processed: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read, T: Incremental> CipherReader<R, T> {
+    pub fn new(inner: R, crypter: T) -> Self {
+        Self {
+            inner,
+            crypter: Some(crypter),
+            raw: vec![0u8; CHUNK_SIZE],
+            processed: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads until some output is available or the crypter is finalized.
+    fn refill(&mut self) -> io::Result<()> {
+        self.processed.clear();
+        self.pos = 0;
+        while self.processed.is_empty() {
+            let crypter = match self.crypter.as_mut() {
+                Some(crypter) => crypter,
+                None => break,
+            };
+            let count = match self.inner.read(&mut self.raw) {
+                Ok(count) => count,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if count == 0 {
+                let crypter = self
+                    .crypter
+                    .take()
+                    .expect("crypter is present until finalized");
+                self.processed = crypter.finalize()?;
+            } else {
+                self.processed = crypter.update(&self.raw[..count]);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read, T: Incremental> Read for CipherReader<R, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos == self.processed.len() {
+            self.refill()?;
+        }
+        let count = buf.len().min(self.processed.len() - self.pos);
+        buf[..count].copy_from_slice(&self.processed[self.pos..self.pos + count]);
+        self.pos += count;
+        Ok(count)
+    }
+}
+
+/// Passes data written to it through an `Encryptor` or `Decryptor` into `inner`.
+///
+/// The final block is written by [`CipherWriter::finish`], dropping the writer
+/// finishes it as well but ignores errors.
+pub struct CipherWriter<W: Write, T: Incremental> {
+    inner: Option<W>,
+    crypter: Option<T>,
+}
+
+impl<W: Write, T: Incremental> CipherWriter<W, T> {
+    pub fn new(inner: W, crypter: T) -> Self {
+        Self {
+            inner: Some(inner),
+            crypter: Some(crypter),
+        }
+    }
+
+    /// Writes the final block, returns underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_tail()?;
+        Ok(self.inner.take().expect("writer is present until finished"))
+    }
+
+    fn write_tail(&mut self) -> io::Result<()> {
+        if let (Some(inner), Some(crypter)) = (self.inner.as_mut(), self.crypter.take()) {
+            inner.write_all(&crypter.finalize()?)?;
+            inner.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write, T: Incremental> Write for CipherWriter<W, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let crypter = self
+            .crypter
+            .as_mut()
+            .expect("crypter is present until finished");
+        let output = crypter.update(buf);
+        self.inner
+            .as_mut()
+            .expect("writer is present until finished")
+            .write_all(&output)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write, T: Incremental> Drop for CipherWriter<W, T> {
+    fn drop(&mut self) {
+        let _ = self.write_tail();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::Aes;
+    use crate::block_cipher::encrypt_cbc;
+    use crate::crypter::{Decryptor, Encryptor, Mode};
+    use crate::padding::{Padding, Pkcs7};
+    use std::io::Cursor;
+
+    #[test]
+    fn cipher_writer_reader_should_pass() {
+        let aes = Aes::new(b"YELLOW SUBMARINE").unwrap();
+        let mode = Mode::Cbc { iv: vec![7; 16] };
+        let plaintext: Vec<u8> = (0..3 * CHUNK_SIZE + 5).map(|i| (i * 31) as u8).collect();
+
+        let mut writer = CipherWriter::new(Vec::new(), Encryptor::new(&aes, mode.clone()));
+        for chunk in plaintext.chunks(1000) {
+            writer.write_all(chunk).unwrap();
+        }
+        let ciphertext = writer.finish().unwrap();
+        assert_eq!(
+            ciphertext,
+            encrypt_cbc(
+                &aes,
+                &Pkcs7::default().pad(&plaintext, 16).unwrap(),
+                &[7; 16]
+            )
+        );
+
+        let mut reader =
+            CipherReader::new(Cursor::new(&ciphertext), Decryptor::new(&aes, mode.clone()));
+        let mut decrypted = Vec::new();
+        reader.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        let mut reader =
+            CipherReader::new(Cursor::new(&plaintext), Encryptor::new(&aes, mode.clone()));
+        let mut encrypted = Vec::new();
+        reader.read_to_end(&mut encrypted).unwrap();
+        assert_eq!(encrypted, ciphertext);
+
+        let mut writer = CipherWriter::new(Vec::new(), Decryptor::new(&aes, mode.clone()));
+        writer
+            .write_all(&ciphertext[..ciphertext.len() - 1])
+            .unwrap();
+        assert_eq!(
+            writer.finish().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let mut reader =
+            CipherReader::new(Cursor::new(&ciphertext[1..]), Decryptor::new(&aes, mode));
+        let result = reader.read_to_end(&mut Vec::new());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,451 @@
+/// Classical ciphers over the Latin alphabet with automatic breakers
+use crate::scoring::{ChiSquared, NgramModel, PlaintextScorer};
+use crate::task_3::ENGLISH_FREQUENCIES;
+use crate::task_6::{
+    split_into_transposed_chunks, KeysizeError, KeysizeEstimator, KeysizeStrategy,
+};
+
+/// Number of best keysizes which are tried by `break_vigenere`.
+const VIGENERE_KEYSIZE_ATTEMPTS: usize = 3;
+/// Widths above this are not tried by `break_columnar`, all column orders are enumerated.
+pub const MAX_COLUMNAR_WIDTH: usize = 8;
+
+/// Ciphers operate on letters, case and other characters are preserved
+/// unless stated otherwise.
+pub trait ClassicalCipher {
+    fn encrypt(&self, plaintext: &str) -> String;
+    fn decrypt(&self, ciphertext: &str) -> String;
+}
+
+/// Cipher recovered by a breaker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Broken<C> {
+    pub cipher: C,
+    pub plaintext: String,
+    pub score: f32,
+}
+
+/// Applies `f` to alphabet index of every letter, `f` also gets index of the letter
+/// among letters of the text.
+fn map_letters<F: FnMut(usize, u8) -> u8>(text: &str, mut f: F) -> String {
+    let mut letter_idx = 0;
+    text.chars()
+        .map(|ch| {
+            let base = match ch {
+                'a'..='z' => b'a',
+                'A'..='Z' => b'A',
+                _ => return ch,
+            };
+            let mapped = f(letter_idx, ch as u8 - base) % 26;
+            letter_idx += 1;
+            (base + mapped) as char
+        })
+        .collect()
+}
+
+fn english_letters() -> ChiSquared {
+    ChiSquared::new(
+        ENGLISH_FREQUENCIES
+            .iter()
+            .filter(|(ch, _)| ch.is_ascii_alphabetic())
+            .map(|(&ch, &freq)| (ch as u8, freq)),
+    )
+}
+
+fn best_of<C, I, S>(candidates: I, ciphertext: &str, scorer: &S) -> Broken<C>
+where
+    C: ClassicalCipher,
+    I: IntoIterator<Item = C>,
+    S: PlaintextScorer + ?Sized,
+{
+    candidates
+        .into_iter()
+        .map(|cipher| {
+            let plaintext = cipher.decrypt(ciphertext);
+            Broken {
+                score: scorer.score(plaintext.as_bytes()),
+                cipher,
+                plaintext,
+            }
+        })
+        .reduce(|best, next| if next.score > best.score { next } else { best })
+        .expect("candidates are not empty")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Caesar {
+    pub shift: u8,
+}
+
+impl Caesar {
+    pub fn new(shift: u8) -> Self {
+        Self { shift: shift % 26 }
+    }
+}
+
+impl ClassicalCipher for Caesar {
+    fn encrypt(&self, plaintext: &str) -> String {
+        map_letters(plaintext, |_, letter| letter + self.shift)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        map_letters(ciphertext, |_, letter| letter + 26 - self.shift)
+    }
+}
+
+pub fn break_caesar(ciphertext: &str) -> Broken<Caesar> {
+    break_caesar_with(ciphertext, &ChiSquared::english())
+}
+
+pub fn break_caesar_with<S>(ciphertext: &str, scorer: &S) -> Broken<Caesar>
+where
+    S: PlaintextScorer + ?Sized,
+{
+    best_of((0..26).map(Caesar::new), ciphertext, scorer)
+}
+
+/// Alphabetic Vigenère cipher, key advances on letters only.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vigenere {
+    shifts: Vec<u8>,
+}
+
+impl Vigenere {
+    /// Key must be a non-empty sequence of letters.
+    pub fn new(key: &str) -> Option<Self> {
+        if key.is_empty() || !key.bytes().all(|b| b.is_ascii_alphabetic()) {
+            return None;
+        }
+        Some(Self {
+            shifts: key.bytes().map(|b| b.to_ascii_uppercase() - b'A').collect(),
+        })
+    }
+
+    pub fn key(&self) -> String {
+        self.shifts.iter().map(|s| (b'A' + s) as char).collect()
+    }
+}
+
+impl ClassicalCipher for Vigenere {
+    fn encrypt(&self, plaintext: &str) -> String {
+        map_letters(plaintext, |idx, letter| {
+            letter + self.shifts[idx % self.shifts.len()]
+        })
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        map_letters(ciphertext, |idx, letter| {
+            letter + 26 - self.shifts[idx % self.shifts.len()]
+        })
+    }
+}
+
+/// Shortest key which repeats into the given one.
+fn minimal_period(shifts: &[u8]) -> &[u8] {
+    (1..=shifts.len())
+        .find(|&period| {
+            shifts.len().is_multiple_of(period)
+                && shifts
+                    .iter()
+                    .zip(shifts.iter().cycle().skip(period))
+                    .all(|(a, b)| a == b)
+        })
+        .map_or(shifts, |period| &shifts[..period])
+}
+
+/// Estimates key length by index of coincidence over letters, then breaks
+/// every column as a Caesar cipher.
+pub fn break_vigenere(ciphertext: &str) -> Result<Broken<Vigenere>, KeysizeError> {
+    break_vigenere_with(ciphertext, &ChiSquared::english())
+}
+
+/// Same as `break_vigenere`, whole plaintexts of different key lengths are rated by
+/// the given scorer.
+pub fn break_vigenere_with<S>(
+    ciphertext: &str,
+    scorer: &S,
+) -> Result<Broken<Vigenere>, KeysizeError>
+where
+    S: PlaintextScorer + ?Sized,
+{
+    let letters = ciphertext
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase())
+        .collect::<Vec<_>>();
+    let estimator = KeysizeEstimator {
+        strategy: KeysizeStrategy::IndexOfCoincidence,
+        min: 1,
+        max: 40,
+    };
+    let column_scorer = english_letters();
+    let keys = estimator
+        .estimate(&letters)?
+        .into_iter()
+        .take(VIGENERE_KEYSIZE_ATTEMPTS)
+        .map(|candidate| {
+            let shifts = split_into_transposed_chunks(&letters, candidate.keysize)
+                .iter()
+                .map(|column| {
+                    let column = String::from_utf8_lossy(column);
+                    break_caesar_with(&column, &column_scorer).cipher.shift
+                })
+                .collect::<Vec<_>>();
+            Vigenere {
+                shifts: minimal_period(&shifts).to_vec(),
+            }
+        });
+    Ok(best_of(keys, ciphertext, scorer))
+}
+
+/// Affine cipher `E(x) = a * x + b mod 26`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Affine {
+    a: u8,
+    b: u8,
+    a_inverse: u8,
+}
+
+impl Affine {
+    /// `a` must be coprime with 26.
+    pub fn new(a: u8, b: u8) -> Option<Self> {
+        let a = a % 26;
+        let a_inverse = (1..26).find(|&inv| (a as u32 * inv as u32) % 26 == 1)?;
+        Some(Self {
+            a,
+            b: b % 26,
+            a_inverse,
+        })
+    }
+
+    pub fn a(&self) -> u8 {
+        self.a
+    }
+
+    pub fn b(&self) -> u8 {
+        self.b
+    }
+}
+
+impl ClassicalCipher for Affine {
+    fn encrypt(&self, plaintext: &str) -> String {
+        map_letters(plaintext, |_, letter| {
+            ((self.a as u32 * letter as u32 + self.b as u32) % 26) as u8
+        })
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        map_letters(ciphertext, |_, letter| {
+            ((self.a_inverse as u32 * (letter as u32 + 26 - self.b as u32)) % 26) as u8
+        })
+    }
+}
+
+pub fn break_affine(ciphertext: &str) -> Broken<Affine> {
+    break_affine_with(ciphertext, &ChiSquared::english())
+}
+
+pub fn break_affine_with<S>(ciphertext: &str, scorer: &S) -> Broken<Affine>
+where
+    S: PlaintextScorer + ?Sized,
+{
+    let keys = (1..26).flat_map(|a| (0..26).filter_map(move |b| Affine::new(a, b)));
+    best_of(keys, ciphertext, scorer)
+}
+
+/// Columnar transposition without padding: text is written in rows of key length
+/// and read column by column in the key order. All characters are transposed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnarTransposition {
+    /// Indices of columns in the reading order.
+    order: Vec<usize>,
+}
+
+impl ColumnarTransposition {
+    /// Columns are read in alphabetical order of key letters, equal letters left to right.
+    pub fn new(keyword: &str) -> Option<Self> {
+        if keyword.is_empty() {
+            return None;
+        }
+        let letters = keyword.to_uppercase().chars().collect::<Vec<_>>();
+        let mut order = (0..letters.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&idx| letters[idx]);
+        Some(Self { order })
+    }
+
+    /// `order` must be a permutation of `0..order.len()`.
+    pub fn from_order(order: Vec<usize>) -> Option<Self> {
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        (!order.is_empty() && sorted.into_iter().eq(0..order.len())).then_some(Self { order })
+    }
+
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Length of the column `idx` for a text of `len` characters.
+    fn column_len(&self, len: usize, idx: usize) -> usize {
+        let width = self.order.len();
+        len / width + usize::from(idx < len % width)
+    }
+}
+
+impl ClassicalCipher for ColumnarTransposition {
+    fn encrypt(&self, plaintext: &str) -> String {
+        let chars = plaintext.chars().collect::<Vec<_>>();
+        let width = self.order.len();
+        self.order
+            .iter()
+            .flat_map(|&column| chars.iter().skip(column).step_by(width))
+            .collect()
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        let chars = ciphertext.chars().collect::<Vec<_>>();
+        let width = self.order.len();
+        let mut result = vec![' '; chars.len()];
+        let mut it = chars.into_iter();
+        for &column in self.order.iter() {
+            for row in 0..self.column_len(result.len(), column) {
+                result[row * width + column] = it.next().expect("column lengths sum up to length");
+            }
+        }
+        result.into_iter().collect()
+    }
+}
+
+/// Calls `f` with every permutation of `items`, Heap's algorithm.
+fn for_each_permutation<F: FnMut(&[usize])>(items: &mut [usize], f: &mut F) {
+    let n = items.len();
+    let mut counters = vec![0; n];
+    f(items);
+    let mut i = 0;
+    while i < n {
+        if counters[i] < i {
+            items.swap(if i % 2 == 0 { 0 } else { counters[i] }, i);
+            f(items);
+            counters[i] += 1;
+            i = 0;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+}
+
+/// Tries all column orders of widths `2..=max_width`, plaintexts are rated
+/// by English trigrams.
+pub fn break_columnar(ciphertext: &str, max_width: usize) -> Broken<ColumnarTransposition> {
+    break_columnar_with(ciphertext, max_width, &NgramModel::english_trigrams())
+}
+
+pub fn break_columnar_with<S>(
+    ciphertext: &str,
+    max_width: usize,
+    scorer: &S,
+) -> Broken<ColumnarTransposition>
+where
+    S: PlaintextScorer + ?Sized,
+{
+    assert!(
+        (2..=MAX_COLUMNAR_WIDTH).contains(&max_width),
+        "Width must be in 2..={}",
+        MAX_COLUMNAR_WIDTH
+    );
+    let mut best: Option<Broken<ColumnarTransposition>> = None;
+    for width in 2..=max_width {
+        let mut order = (0..width).collect::<Vec<_>>();
+        for_each_permutation(&mut order, &mut |order| {
+            let cipher = ColumnarTransposition {
+                order: order.to_vec(),
+            };
+            let plaintext = cipher.decrypt(ciphertext);
+            let score = scorer.score(plaintext.as_bytes());
+            if best.as_ref().is_none_or(|b| score > b.score) {
+                best = Some(Broken {
+                    cipher,
+                    plaintext,
+                    score,
+                });
+            }
+        });
+    }
+    best.expect("at least one width is tried")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::load_base64_file;
+    use crate::task_5::repeating_key_xor;
+
+    /// Plaintext of the task 6.
+    fn lyrics() -> String {
+        let cipher = load_base64_file("res/task6.txt").unwrap();
+        let plaintext = repeating_key_xor(&cipher[..], b"Terminator X: Bring the noise");
+        String::from_utf8(plaintext).unwrap()
+    }
+
+    #[test]
+    fn caesar_and_affine_should_pass() {
+        let caesar = Caesar::new(3);
+        assert_eq!(caesar.encrypt("Hello, World!"), "Khoor, Zruog!");
+        assert_eq!(caesar.decrypt("Khoor, Zruog!"), "Hello, World!");
+        let plaintext = "The quick brown fox jumps over the lazy dog and runs away from the hunter";
+        let broken = break_caesar(&Caesar::new(17).encrypt(plaintext));
+        assert_eq!(
+            (broken.cipher, broken.plaintext.as_str()),
+            (Caesar::new(17), plaintext)
+        );
+
+        let affine = Affine::new(5, 8).unwrap();
+        assert_eq!(affine.encrypt("AFFINE cipher"), "IHHWVC swfrcp");
+        assert_eq!(affine.decrypt("IHHWVC swfrcp"), "AFFINE cipher");
+        assert!(Affine::new(13, 1).is_none());
+        let broken = break_affine(&Affine::new(7, 3).unwrap().encrypt(plaintext));
+        assert_eq!((broken.cipher.a(), broken.cipher.b()), (7, 3));
+        assert_eq!(broken.plaintext, plaintext);
+    }
+
+    #[test]
+    fn vigenere_should_pass() {
+        let vigenere = Vigenere::new("lemon").unwrap();
+        assert_eq!(vigenere.encrypt("ATTACK at dawn!"), "LXFOPV ef rnhr!");
+        assert_eq!(vigenere.decrypt("LXFOPV ef rnhr!"), "ATTACK at dawn!");
+        assert!(Vigenere::new("").is_none());
+        assert!(Vigenere::new("key 2").is_none());
+
+        let plaintext = lyrics();
+        let ciphertext = Vigenere::new("CRYPTOPALS").unwrap().encrypt(&plaintext);
+        let broken = break_vigenere(&ciphertext).unwrap();
+        assert_eq!(broken.cipher.key(), "CRYPTOPALS");
+        assert_eq!(broken.plaintext, plaintext);
+        assert!(matches!(
+            break_vigenere("a"),
+            Err(KeysizeError::CipherTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn columnar_transposition_should_pass() {
+        let zebras = ColumnarTransposition::new("ZEBRAS").unwrap();
+        assert_eq!(zebras.order(), &[4, 2, 1, 3, 5, 0]);
+        assert_eq!(
+            zebras.encrypt("WEAREDISCOVEREDFLEEATONCE"),
+            "EVLNACDTESEAROFODEECWIREE"
+        );
+        assert_eq!(
+            zebras.decrypt("EVLNACDTESEAROFODEECWIREE"),
+            "WEAREDISCOVEREDFLEEATONCE"
+        );
+        assert!(ColumnarTransposition::from_order(vec![0, 2]).is_none());
+
+        let plaintext = "There is nothing either good or bad, but thinking makes it so. \
+                         The rest is silence, and the readiness is all.";
+        let cipher = ColumnarTransposition::new("SHAKE").unwrap();
+        let broken = break_columnar(&cipher.encrypt(plaintext), 6);
+        assert_eq!(broken.cipher, cipher);
+        assert_eq!(broken.plaintext, plaintext);
+    }
+}
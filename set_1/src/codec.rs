@@ -0,0 +1,499 @@
+/// Common interface for text encodings of binary data: base64, hex, base32 and base85
+use crate::task_1::{Base64Config, Base64Error};
+use std::fmt::Display;
+
+pub trait Codec {
+    type Error: std::error::Error;
+
+    fn encode(&self, data: &[u8]) -> String;
+    fn decode(&self, input: &str) -> Result<Vec<u8>, Self::Error>;
+}
+
+impl Codec for Base64Config {
+    type Error = Base64Error;
+
+    fn encode(&self, data: &[u8]) -> String {
+        Base64Config::encode(self, data)
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, Base64Error> {
+        Base64Config::decode(self, input)
+    }
+}
+
+/// Decoding errors of hex, base32 and base85 codecs, `offset` is a byte offset in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    /// Input contains a char which is not valid at this position.
+    InvalidChar { offset: usize, ch: char },
+    /// Input ends with an incomplete group which starts at `offset`.
+    InvalidLength { offset: usize },
+    /// Group starting at `offset` encodes a value which doesn't fit its bytes.
+    Overflow { offset: usize },
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            CodecError::InvalidChar { offset, ch } => {
+                write!(f, "unexpected char {:?} at offset {}", ch, offset)
+            }
+            CodecError::InvalidLength { offset } => {
+                write!(f, "incomplete group at offset {}", offset)
+            }
+            CodecError::Overflow { offset } => {
+                write!(f, "group at offset {} is out of range", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+fn invalid_char(input: &str, offset: usize) -> CodecError {
+    let ch = input[offset..].chars().next().unwrap_or('\0');
+    CodecError::InvalidChar { offset, ch }
+}
+
+/// Hex codec, decoding tolerates whitespace, `0x` prefixes and colon-separated bytes,
+/// e.g. `de:ad 0xbe EF`, separators are accepted only between bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hex {
+    pub uppercase: bool,
+}
+
+impl Codec for Hex {
+    type Error = CodecError;
+
+    fn encode(&self, data: &[u8]) -> String {
+        let digits: &[u8; 16] = if self.uppercase {
+            b"0123456789ABCDEF"
+        } else {
+            b"0123456789abcdef"
+        };
+        let mut result = String::with_capacity(data.len() * 2);
+        for &b in data.iter() {
+            result.push(digits[(b >> 4) as usize] as char);
+            result.push(digits[(b & 0x0f) as usize] as char);
+        }
+        result
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, CodecError> {
+        let bytes = input.as_bytes();
+        let is_separator = |b: u8| b.is_ascii_whitespace() || b == b':';
+        let mut result = Vec::with_capacity(bytes.len() / 2);
+        let mut high: Option<(usize, u8)> = None;
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let b = bytes[offset];
+            let token_start = offset == 0 || is_separator(bytes[offset - 1]);
+            if token_start && b == b'0' && matches!(bytes.get(offset + 1), Some(b'x' | b'X')) {
+                offset += 2;
+                continue;
+            }
+            match ((b as char).to_digit(16), high) {
+                (Some(low), Some((_, h))) => {
+                    result.push(h << 4 | low as u8);
+                    high = None;
+                }
+                (Some(nibble), None) => high = Some((offset, nibble as u8)),
+                (None, None) if is_separator(b) => (),
+                (None, _) => return Err(invalid_char(input, offset)),
+            }
+            offset += 1;
+        }
+        match high {
+            Some((offset, _)) => Err(CodecError::InvalidLength { offset }),
+            None => Ok(result),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base32Alphabet {
+    /// RFC 4648 `A-Z2-7` alphabet.
+    Standard,
+    /// RFC 4648 "extended hex" `0-9A-V` alphabet, preserves sort order.
+    Hex,
+}
+
+impl Base32Alphabet {
+    pub fn chars(self) -> &'static [u8; 32] {
+        match self {
+            Base32Alphabet::Standard => b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+            Base32Alphabet::Hex => b"0123456789ABCDEFGHIJKLMNOPQRSTUV",
+        }
+    }
+
+    /// Decoding is case-insensitive.
+    fn index(self, input: u8) -> Option<u8> {
+        let input = input.to_ascii_uppercase();
+        self.chars()
+            .iter()
+            .position(|&ch| ch == input)
+            .map(|idx| idx as u8)
+    }
+}
+
+/// RFC 4648 base32, 5-byte groups are encoded as 8 chars.
+///
+/// Unpadded input is always accepted when decoding, padded input is accepted
+/// if padding length matches the last group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Base32 {
+    pub alphabet: Base32Alphabet,
+    pub pad: bool,
+}
+
+impl Base32 {
+    pub const STANDARD: Base32 = Base32 {
+        alphabet: Base32Alphabet::Standard,
+        pad: true,
+    };
+    pub const HEX: Base32 = Base32 {
+        alphabet: Base32Alphabet::Hex,
+        pad: true,
+    };
+}
+
+impl Default for Base32 {
+    fn default() -> Self {
+        Base32::STANDARD
+    }
+}
+
+impl Codec for Base32 {
+    type Error = CodecError;
+
+    fn encode(&self, data: &[u8]) -> String {
+        let chars = self.alphabet.chars();
+        let mut result = String::with_capacity(data.len().div_ceil(5) * 8);
+        for chunk in data.chunks(5) {
+            let mut group = [0u8; 5];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let value = group.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+            // each byte needs ceil(bits / 5) chars
+            let used = (chunk.len() * 8).div_ceil(5);
+            result
+                .extend((0..used).map(|i| chars[(value >> (35 - 5 * i) & 0x1f) as usize] as char));
+            if self.pad {
+                result.extend((used..8).map(|_| '='));
+            }
+        }
+        result
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, CodecError> {
+        let bytes = input.as_bytes();
+        let data_len = bytes.len() - bytes.iter().rev().take_while(|&&b| b == b'=').count();
+        if data_len < bytes.len() && data_len.is_multiple_of(8) {
+            return Err(invalid_char(input, data_len));
+        }
+        let mut result = Vec::with_capacity(data_len * 5 / 8);
+        for (group_idx, group) in bytes[..data_len].chunks(8).enumerate() {
+            let group_offset = group_idx * 8;
+            let mut value = 0u64;
+            for (i, &b) in group.iter().enumerate() {
+                let idx = self
+                    .alphabet
+                    .index(b)
+                    .ok_or_else(|| invalid_char(input, group_offset + i))?;
+                value |= (idx as u64) << (35 - 5 * i);
+            }
+            // valid partial groups are 2, 4, 5 and 7 chars long
+            let count = match group.len() {
+                8 => 5,
+                7 => 4,
+                5 => 3,
+                4 => 2,
+                2 => 1,
+                _ => {
+                    return Err(CodecError::InvalidLength {
+                        offset: group_offset,
+                    })
+                }
+            };
+            let padded_len = if data_len < bytes.len() {
+                8
+            } else {
+                group.len()
+            };
+            if group.len() < 8 && bytes.len() - group_offset != padded_len {
+                return Err(invalid_char(input, group_offset + group.len()));
+            }
+            result.extend((0..count).map(|i| (value >> (32 - 8 * i)) as u8));
+        }
+        Ok(result)
+    }
+}
+
+/// Adobe Ascii85, 4-byte groups are encoded as 5 chars from `!` to `u`,
+/// all-zero groups are shortened to `z`.
+///
+/// Decoding skips whitespace and optional `<~ ~>` delimiters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ascii85 {
+    /// Wrap encoded output in `<~ ~>`.
+    pub delimiters: bool,
+}
+
+/// ZeroMQ Z85, string-safe base85 alphabet.
+///
+/// Spec allows only input sizes divisible by 4, here a partial last group is
+/// encoded the same way Ascii85 does it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Z85;
+
+const Z85_CHARS: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+fn encode_base85(data: &[u8], digit: impl Fn(u32) -> char, zero_group: Option<char>) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(4) * 5);
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(group);
+        if let (Some(z), 4, 0) = (zero_group, chunk.len(), value) {
+            result.push(z);
+            continue;
+        }
+        let mut digits = [0u32; 5];
+        for d in digits.iter_mut().rev() {
+            *d = value % 85;
+            value /= 85;
+        }
+        result.extend(digits[..chunk.len() + 1].iter().map(|&d| digit(d)));
+    }
+    result
+}
+
+/// Decodes `(offset, byte)` pairs, partial last group is padded with the highest digit.
+fn decode_base85(
+    input: &str,
+    chars: impl Iterator<Item = (usize, u8)>,
+    index: impl Fn(u8) -> Option<u8>,
+    zero_group: Option<u8>,
+) -> Result<Vec<u8>, CodecError> {
+    let mut result = Vec::new();
+    let mut group = [84u8; 5];
+    let mut filled = 0;
+    let mut group_offset = 0;
+    let mut push_group = |group: &[u8; 5], count: usize, offset: usize| {
+        let value = group
+            .iter()
+            .try_fold(0u32, |acc, &d| acc.checked_mul(85)?.checked_add(d as u32))
+            .ok_or(CodecError::Overflow { offset })?;
+        result.extend_from_slice(&value.to_be_bytes()[..count]);
+        Ok(())
+    };
+    for (offset, b) in chars {
+        if filled == 0 {
+            group_offset = offset;
+            if Some(b) == zero_group {
+                push_group(&[0; 5], 4, offset)?;
+                continue;
+            }
+        }
+        group[filled] = index(b).ok_or_else(|| invalid_char(input, offset))?;
+        filled += 1;
+        if filled == 5 {
+            push_group(&group, 4, group_offset)?;
+            filled = 0;
+        }
+    }
+    match filled {
+        0 => (),
+        1 => {
+            return Err(CodecError::InvalidLength {
+                offset: group_offset,
+            })
+        }
+        _ => {
+            group[filled..].fill(84);
+            push_group(&group, filled - 1, group_offset)?;
+        }
+    }
+    Ok(result)
+}
+
+impl Codec for Ascii85 {
+    type Error = CodecError;
+
+    fn encode(&self, data: &[u8]) -> String {
+        let encoded = encode_base85(data, |d| (b'!' + d as u8) as char, Some('z'));
+        if self.delimiters {
+            format!("<~{}~>", encoded)
+        } else {
+            encoded
+        }
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, CodecError> {
+        if input.trim().is_empty() {
+            return Ok(vec![]);
+        }
+        let trimmed = input.trim_end();
+        let start = input.len() - input.trim_start().len();
+        let (start, end) = match (input[start..].starts_with("<~"), trimmed.ends_with("~>")) {
+            (true, true) if start + 2 <= trimmed.len() - 2 => (start + 2, trimmed.len() - 2),
+            (false, false) => (start, trimmed.len()),
+            (true, _) => return Err(invalid_char(input, start)),
+            (false, true) => return Err(invalid_char(input, trimmed.len() - 2)),
+        };
+        let chars = input.as_bytes()[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| (start + i, b))
+            .filter(|(_, b)| !b.is_ascii_whitespace());
+        decode_base85(
+            input,
+            chars,
+            |b| (b'!'..=b'u').contains(&b).then(|| b - b'!'),
+            Some(b'z'),
+        )
+    }
+}
+
+impl Codec for Z85 {
+    type Error = CodecError;
+
+    fn encode(&self, data: &[u8]) -> String {
+        encode_base85(data, |d| Z85_CHARS[d as usize] as char, None)
+    }
+
+    fn decode(&self, input: &str) -> Result<Vec<u8>, CodecError> {
+        decode_base85(
+            input,
+            input.bytes().enumerate(),
+            |b| {
+                Z85_CHARS
+                    .iter()
+                    .position(|&ch| ch == b)
+                    .map(|idx| idx as u8)
+            },
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<C: Codec>(codec: &C)
+    where
+        C::Error: std::fmt::Debug,
+    {
+        for len in 0..=20 {
+            let data = (0..len).map(|i| (i * 37 + 5) as u8).collect::<Vec<_>>();
+            assert_eq!(codec.decode(&codec.encode(&data)).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn hex_codec_should_pass() {
+        roundtrip(&Hex::default());
+        roundtrip(&Hex { uppercase: true });
+        assert_eq!(Hex { uppercase: true }.encode(b"\xde\xad"), "DEAD");
+        assert_eq!(Hex::default().encode(b"\xde\xad"), "dead");
+        assert_eq!(
+            Hex::default().decode("de:ad 0xbe\n0XEF\t00").unwrap(),
+            b"\xde\xad\xbe\xef\x00"
+        );
+        assert_eq!(
+            Hex::default().decode("d e"),
+            Err(CodecError::InvalidChar { offset: 1, ch: ' ' })
+        );
+        assert_eq!(
+            Hex::default().decode("dea"),
+            Err(CodecError::InvalidLength { offset: 2 })
+        );
+        assert_eq!(
+            Hex::default().decode("de0xad"),
+            Err(CodecError::InvalidChar { offset: 3, ch: 'x' })
+        );
+    }
+
+    #[test]
+    fn base32_codec_should_pass() {
+        roundtrip(&Base32::STANDARD);
+        roundtrip(&Base32::HEX);
+        roundtrip(&Base32 {
+            pad: false,
+            ..Base32::STANDARD
+        });
+        // RFC 4648 test vectors
+        let vectors = [
+            ("", "", ""),
+            ("f", "MY======", "CO======"),
+            ("fo", "MZXQ====", "CPNG===="),
+            ("foo", "MZXW6===", "CPNMU==="),
+            ("foob", "MZXW6YQ=", "CPNMUOG="),
+            ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+            ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======"),
+        ];
+        for (data, encoded, encoded_hex) in vectors {
+            assert_eq!(Base32::STANDARD.encode(data.as_bytes()), encoded);
+            assert_eq!(Base32::HEX.encode(data.as_bytes()), encoded_hex);
+            assert_eq!(Base32::STANDARD.decode(encoded).unwrap(), data.as_bytes());
+            assert_eq!(Base32::HEX.decode(encoded_hex).unwrap(), data.as_bytes());
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(Base32::STANDARD.decode(unpadded).unwrap(), data.as_bytes());
+        }
+        assert_eq!(Base32::STANDARD.decode("mzxw6ytb").unwrap(), b"fooba");
+        assert_eq!(
+            Base32::STANDARD.decode("MZX"),
+            Err(CodecError::InvalidLength { offset: 0 })
+        );
+        assert_eq!(
+            Base32::STANDARD.decode("MZ1W6YTB"),
+            Err(CodecError::InvalidChar { offset: 2, ch: '1' })
+        );
+        assert_eq!(
+            Base32::STANDARD.decode("MZXW6YTB========"),
+            Err(CodecError::InvalidChar { offset: 8, ch: '=' })
+        );
+        assert_eq!(
+            Base32::STANDARD.decode("MZXQ=="),
+            Err(CodecError::InvalidChar { offset: 4, ch: '=' })
+        );
+    }
+
+    #[test]
+    fn base85_codecs_should_pass() {
+        roundtrip(&Ascii85::default());
+        roundtrip(&Ascii85 { delimiters: true });
+        roundtrip(&Z85);
+
+        let ascii85 = Ascii85 { delimiters: true };
+        assert_eq!(ascii85.encode(b"Man "), "<~9jqo^~>");
+        assert_eq!(ascii85.encode(b"sure."), "<~F*2M7/c~>");
+        assert_eq!(ascii85.encode(&[0, 0, 0, 0, 0]), "<~z!!~>");
+        assert_eq!(ascii85.decode(" <~F*2M7\n/c~>\n").unwrap(), b"sure.");
+        assert_eq!(Ascii85::default().decode("F*2M7/c").unwrap(), b"sure.");
+        assert_eq!(Ascii85::default().decode("  "), Ok(vec![]));
+        assert_eq!(ascii85.decode(" \n"), Ok(vec![]));
+        assert_eq!(
+            Ascii85::default().decode("s8W-\""),
+            Err(CodecError::Overflow { offset: 0 })
+        );
+        assert_eq!(
+            Ascii85::default().decode("F*2M7/"),
+            Err(CodecError::InvalidLength { offset: 5 })
+        );
+        assert_eq!(
+            ascii85.decode("<~F*2M7/c"),
+            Err(CodecError::InvalidChar { offset: 0, ch: '<' })
+        );
+
+        // ZeroMQ RFC 32 test vector
+        let data = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+        assert_eq!(Z85.encode(&data), "HelloWorld");
+        assert_eq!(Z85.decode("HelloWorld").unwrap(), data);
+        assert_eq!(
+            Z85.decode("Hello~orld"),
+            Err(CodecError::InvalidChar { offset: 5, ch: '~' })
+        );
+    }
+}
@@ -0,0 +1,436 @@
+/// Incremental encryption and decryption for every mode of operation
+use crate::block_cipher::{xor_in_place, BlockCipher};
+use crate::ctr::CounterLayout;
+use crate::padding::{Padding, PaddingError, Pkcs7};
+
+/// Mode of operation along with its IV or initial counter block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Ecb,
+    Cbc {
+        iv: Vec<u8>,
+    },
+    Pcbc {
+        iv: Vec<u8>,
+    },
+    /// Full-block feedback, same as `encrypt_cfb`.
+    Cfb {
+        iv: Vec<u8>,
+    },
+    Cfb8 {
+        iv: Vec<u8>,
+    },
+    Ofb {
+        iv: Vec<u8>,
+    },
+    Ctr {
+        initial: Vec<u8>,
+        layout: CounterLayout,
+    },
+}
+
+impl Mode {
+    /// Stream modes map every byte to a byte and need no padding.
+    pub fn is_stream(&self) -> bool {
+        !matches!(self, Mode::Ecb | Mode::Cbc { .. } | Mode::Pcbc { .. })
+    }
+
+    fn iv(&self) -> &[u8] {
+        match self {
+            Mode::Ecb => &[],
+            Mode::Cbc { iv }
+            | Mode::Pcbc { iv }
+            | Mode::Cfb { iv }
+            | Mode::Cfb8 { iv }
+            | Mode::Ofb { iv } => iv,
+            Mode::Ctr { initial, .. } => initial,
+        }
+    }
+}
+
+/// Chaining state shared by both directions.
+#[derive(Clone, Debug)]
+struct ModeState<C: BlockCipher> {
+    cipher: C,
+    mode: Mode,
+    /// Previous ciphertext block, feedback register or initial counter block.
+    register: Vec<u8>,
+    keystream: Vec<u8>,
+    /// Keystream bytes already used, a whole block means a new one is needed.
+    used: usize,
+    /// Counter blocks generated so far.
+    blocks: u64,
+}
+
+impl<C: BlockCipher> ModeState<C> {
+    fn new(cipher: C, mode: Mode) -> Self {
+        let block_size = cipher.block_size();
+        if mode != Mode::Ecb {
+            assert_eq!(mode.iv().len(), block_size, "IV must be of block size");
+        }
+        if let Mode::Ctr { layout, .. } = mode {
+            assert!(
+                block_size >= layout.counter_len(),
+                "Counter doesn't fit into block"
+            );
+        }
+        Self {
+            register: mode.iv().to_vec(),
+            keystream: vec![0; block_size],
+            used: block_size,
+            blocks: 0,
+            cipher,
+            mode,
+        }
+    }
+
+    fn block_size(&self) -> usize {
+        self.cipher.block_size()
+    }
+
+    /// Processes whole blocks in place, only used by padded modes.
+    fn process_blocks(&mut self, data: &mut [u8], encrypt: bool) {
+        let block_size = self.block_size();
+        for block in data.chunks_exact_mut(block_size) {
+            let input = block.to_vec();
+            match (&self.mode, encrypt) {
+                (Mode::Ecb, true) => self.cipher.encrypt_block(block),
+                (Mode::Ecb, false) => self.cipher.decrypt_block(block),
+                (Mode::Cbc { .. } | Mode::Pcbc { .. }, true) => {
+                    xor_in_place(block, &self.register);
+                    self.cipher.encrypt_block(block);
+                }
+                (Mode::Cbc { .. } | Mode::Pcbc { .. }, false) => {
+                    self.cipher.decrypt_block(block);
+                    xor_in_place(block, &self.register);
+                }
+                _ => unreachable!("stream modes are processed bytewise"),
+            }
+            match self.mode {
+                Mode::Cbc { .. } => {
+                    self.register = if encrypt { block.to_vec() } else { input };
+                }
+                Mode::Pcbc { .. } => {
+                    self.register = input;
+                    xor_in_place(&mut self.register, block);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Processes bytes of a stream mode, keystream left over from a
+    /// previous call is used first.
+    fn process_stream(&mut self, data: &mut [u8], encrypt: bool) {
+        let block_size = self.block_size();
+        for byte in data {
+            if let Mode::Cfb8 { .. } = self.mode {
+                let mut keystream = self.register.clone();
+                self.cipher.encrypt_block(&mut keystream);
+                let input = *byte;
+                *byte ^= keystream[0];
+                self.register.rotate_left(1);
+                self.register[block_size - 1] = if encrypt { *byte } else { input };
+                continue;
+            }
+            if self.used == block_size {
+                self.next_keystream();
+            }
+            let input = *byte;
+            *byte ^= self.keystream[self.used];
+            if let Mode::Cfb { .. } = self.mode {
+                self.register[self.used] = if encrypt { *byte } else { input };
+            }
+            self.used += 1;
+        }
+    }
+
+    fn next_keystream(&mut self) {
+        match self.mode {
+            Mode::Cfb { .. } => {
+                self.keystream.copy_from_slice(&self.register);
+                self.cipher.encrypt_block(&mut self.keystream);
+            }
+            Mode::Ofb { .. } => {
+                self.cipher.encrypt_block(&mut self.register);
+                self.keystream.copy_from_slice(&self.register);
+            }
+            Mode::Ctr { layout, .. } => {
+                self.keystream.copy_from_slice(&self.register);
+                layout.advance(&mut self.keystream, self.blocks);
+                self.cipher.encrypt_block(&mut self.keystream);
+                self.blocks += 1;
+            }
+            _ => unreachable!("only feedback and counter modes have a keystream"),
+        }
+        self.used = 0;
+    }
+}
+
+/// Common interface of `Encryptor` and `Decryptor`, used by the IO adapters.
+pub trait Incremental {
+    /// Output may lag behind the input until [`Incremental::finalize`].
+    fn update(&mut self, data: &[u8]) -> Vec<u8>;
+    /// Flushes buffered input and applies or removes padding.
+    fn finalize(self) -> Result<Vec<u8>, PaddingError>;
+}
+
+/// Stateful encryption, ECB, CBC and PCBC are padded with `P` on [`Encryptor::finalize`].
+///
+/// Unlike the one-shot functions the input doesn't have to be available up front,
+/// only a partial block is kept between calls.
+#[derive(Clone, Debug)]
+pub struct Encryptor<C: BlockCipher, P: Padding = Pkcs7> {
+    state: ModeState<C>,
+    padding: P,
+    /// Input bytes which don't form a full block yet.
+    pending: Vec<u8>,
+}
+
+impl<C: BlockCipher> Encryptor<C> {
+    pub fn new(cipher: C, mode: Mode) -> Self {
+        Self::with_padding(cipher, mode, Pkcs7::default())
+    }
+}
+
+impl<C: BlockCipher, P: Padding> Encryptor<C, P> {
+    /// Padding is ignored for stream modes.
+    pub fn with_padding(cipher: C, mode: Mode, padding: P) -> Self {
+        Self {
+            state: ModeState::new(cipher, mode),
+            padding,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        if self.state.mode.is_stream() {
+            let mut output = data.to_vec();
+            self.state.process_stream(&mut output, true);
+            return output;
+        }
+        self.pending.extend_from_slice(data);
+        let aligned = self.pending.len() - self.pending.len() % self.state.block_size();
+        let mut output: Vec<u8> = self.pending.drain(..aligned).collect();
+        self.state.process_blocks(&mut output, true);
+        output
+    }
+
+    /// Pads and encrypts the remaining partial block, fails if the padding
+    /// doesn't support the block size of the cipher.
+    pub fn finalize(mut self) -> Result<Vec<u8>, PaddingError> {
+        if self.state.mode.is_stream() {
+            return Ok(Vec::new());
+        }
+        let mut output = self.padding.pad(&self.pending, self.state.block_size())?;
+        self.state.process_blocks(&mut output, true);
+        Ok(output)
+    }
+}
+
+impl<C: BlockCipher, P: Padding> Incremental for Encryptor<C, P> {
+    fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        Encryptor::update(self, data)
+    }
+
+    fn finalize(self) -> Result<Vec<u8>, PaddingError> {
+        Encryptor::finalize(self)
+    }
+}
+
+/// Stateful decryption, the last block of padded modes is held back until
+/// [`Decryptor::finalize`] removes the padding.
+#[derive(Clone, Debug)]
+pub struct Decryptor<C: BlockCipher, P: Padding = Pkcs7> {
+    state: ModeState<C>,
+    padding: P,
+    /// Ciphertext not decrypted yet, at most one full block after each update.
+    pending: Vec<u8>,
+    /// Ciphertext bytes seen so far, reported on length errors.
+    total: usize,
+}
+
+impl<C: BlockCipher> Decryptor<C> {
+    pub fn new(cipher: C, mode: Mode) -> Self {
+        Self::with_padding(cipher, mode, Pkcs7::default())
+    }
+}
+
+impl<C: BlockCipher, P: Padding> Decryptor<C, P> {
+    /// Padding is ignored for stream modes.
+    pub fn with_padding(cipher: C, mode: Mode, padding: P) -> Self {
+        Self {
+            state: ModeState::new(cipher, mode),
+            padding,
+            pending: Vec::new(),
+            total: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        self.total += data.len();
+        if self.state.mode.is_stream() {
+            let mut output = data.to_vec();
+            self.state.process_stream(&mut output, false);
+            return output;
+        }
+        self.pending.extend_from_slice(data);
+        let block_size = self.state.block_size();
+        // keep the last block, even a full one, it may be the padding
+        let ready = self.pending.len().saturating_sub(1) / block_size * block_size;
+        let mut output: Vec<u8> = self.pending.drain(..ready).collect();
+        self.state.process_blocks(&mut output, false);
+        output
+    }
+
+    /// Decrypts the held back block and removes padding from it.
+    pub fn finalize(mut self) -> Result<Vec<u8>, PaddingError> {
+        if self.state.mode.is_stream() {
+            return Ok(Vec::new());
+        }
+        let block_size = self.state.block_size();
+        if self.total == 0 {
+            // some schemes, e.g. zero padding, produce no output for empty input
+            return self.padding.unpad(&[], block_size);
+        }
+        if self.pending.len() != block_size {
+            return Err(PaddingError::InvalidLength {
+                len: self.total,
+                block_size,
+            });
+        }
+        let mut last_block = std::mem::take(&mut self.pending);
+        self.state.process_blocks(&mut last_block, false);
+        self.padding.unpad(&last_block, block_size)
+    }
+}
+
+impl<C: BlockCipher, P: Padding> Incremental for Decryptor<C, P> {
+    fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        Decryptor::update(self, data)
+    }
+
+    fn finalize(self) -> Result<Vec<u8>, PaddingError> {
+        Decryptor::finalize(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::Aes;
+    use crate::block_cipher::*;
+    use crate::ctr::Ctr;
+    use crate::padding::{Iso7816, ZeroPadding};
+
+    /// Feeds `data` in chunks of growing size.
+    fn chunked<T: Incremental>(mut crypter: T, data: &[u8]) -> Result<Vec<u8>, PaddingError> {
+        let mut output = Vec::new();
+        let mut rest = data;
+        let mut size = 0;
+        while !rest.is_empty() {
+            size = (size + 1) % 23;
+            let (chunk, tail) = rest.split_at(size.min(rest.len()));
+            output.extend(crypter.update(chunk));
+            rest = tail;
+        }
+        output.extend(crypter.finalize()?);
+        Ok(output)
+    }
+
+    #[test]
+    fn incremental_modes_should_pass() {
+        let aes = Aes::new(b"YELLOW SUBMARINE").unwrap();
+        let iv = b"initial vector!!".to_vec();
+        for len in [0, 1, 15, 16, 17, 100] {
+            let plaintext: Vec<u8> = (0..len as u8).collect();
+            let padded = Pkcs7::default().pad(&plaintext, 16).unwrap();
+            let cases = [
+                (Mode::Ecb, encrypt_ecb(&aes, &padded)),
+                (
+                    Mode::Cbc { iv: iv.clone() },
+                    encrypt_cbc(&aes, &padded, &iv),
+                ),
+                (
+                    Mode::Pcbc { iv: iv.clone() },
+                    encrypt_pcbc(&aes, &padded, &iv),
+                ),
+                (
+                    Mode::Cfb { iv: iv.clone() },
+                    encrypt_cfb(&aes, &plaintext, &iv),
+                ),
+                (
+                    Mode::Cfb8 { iv: iv.clone() },
+                    encrypt_cfb8(&aes, &plaintext, &iv),
+                ),
+                (
+                    Mode::Ofb { iv: iv.clone() },
+                    encrypt_ofb(&aes, &plaintext, &iv),
+                ),
+                (
+                    Mode::Ctr {
+                        initial: iv.clone(),
+                        layout: CounterLayout::Nist,
+                    },
+                    Ctr::new(&aes, &iv, CounterLayout::Nist).process(&plaintext),
+                ),
+            ];
+            for (mode, expected) in cases {
+                let ciphertext = chunked(Encryptor::new(&aes, mode.clone()), &plaintext);
+                assert_eq!(ciphertext.as_ref(), Ok(&expected), "{:?}", mode);
+                assert_eq!(
+                    chunked(Decryptor::new(&aes, mode.clone()), &expected),
+                    Ok(plaintext.clone()),
+                    "{:?}",
+                    mode
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_padding_errors_should_pass() {
+        let aes = Aes::new(b"YELLOW SUBMARINE").unwrap();
+        let mode = Mode::Cbc { iv: vec![0; 16] };
+        let mut encryptor = Encryptor::with_padding(&aes, mode.clone(), Iso7816);
+        assert_eq!(encryptor.update(b"ICE ICE BABY"), b"");
+        let ciphertext = encryptor.finalize().unwrap();
+        assert_eq!(ciphertext.len(), 16);
+
+        let mut decryptor = Decryptor::with_padding(&aes, mode.clone(), Iso7816);
+        assert_eq!(decryptor.update(&ciphertext), b"");
+        assert_eq!(decryptor.finalize(), Ok(b"ICE ICE BABY".to_vec()));
+        // Iso7816 pad doesn't survive PKCS#7 removal
+        assert_eq!(
+            chunked(Decryptor::new(&aes, mode.clone()), &ciphertext),
+            Err(PaddingError::BadPadding)
+        );
+        assert_eq!(
+            chunked(Decryptor::new(&aes, mode.clone()), &ciphertext[..15]),
+            Err(PaddingError::InvalidLength {
+                len: 15,
+                block_size: 16
+            })
+        );
+        // zero padding of an empty message produces no ciphertext at all
+        let empty = chunked(
+            Encryptor::with_padding(&aes, mode.clone(), ZeroPadding),
+            b"",
+        );
+        assert_eq!(empty, Ok(vec![]));
+        assert_eq!(
+            chunked(
+                Decryptor::with_padding(&aes, mode.clone(), ZeroPadding),
+                b""
+            ),
+            Ok(vec![])
+        );
+        assert_eq!(
+            chunked(Decryptor::new(&aes, mode), b""),
+            Err(PaddingError::InvalidLength {
+                len: 0,
+                block_size: 16
+            })
+        );
+    }
+}
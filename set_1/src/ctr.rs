@@ -0,0 +1,226 @@
+/// Counter mode keystream over any block cipher with random-access seeking
+use crate::block_cipher::BlockCipher;
+
+/// Which trailing bytes of the counter block are incremented and how.
+/// Bytes in front of the counter field hold the nonce and never change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CounterLayout {
+    /// 64-bit nonce followed by 64-bit little-endian counter, as in cryptopals.
+    Cryptopals,
+    /// 96-bit nonce followed by 32-bit big-endian counter, as in NIST SP 800-38D.
+    Nist,
+    /// Whole 128-bit block is a big-endian counter.
+    Full128,
+}
+
+impl CounterLayout {
+    /// Length of the counter field in bytes.
+    pub fn counter_len(&self) -> usize {
+        match self {
+            CounterLayout::Cryptopals => 8,
+            CounterLayout::Nist => 4,
+            CounterLayout::Full128 => 16,
+        }
+    }
+
+    /// Adds `blocks` to the counter field of `block` modulo its size.
+    pub(crate) fn advance(&self, block: &mut [u8], blocks: u64) {
+        let field_start = block.len() - self.counter_len();
+        let field = &mut block[field_start..];
+        match self {
+            CounterLayout::Cryptopals => {
+                let counter = u64::from_le_bytes(field.try_into().expect("8 bytes"));
+                field.copy_from_slice(&counter.wrapping_add(blocks).to_le_bytes());
+            }
+            CounterLayout::Nist => {
+                let counter = u32::from_be_bytes(field.try_into().expect("4 bytes"));
+                field.copy_from_slice(&counter.wrapping_add(blocks as u32).to_be_bytes());
+            }
+            CounterLayout::Full128 => {
+                let counter = u128::from_be_bytes(field.try_into().expect("16 bytes"));
+                field.copy_from_slice(&counter.wrapping_add(blocks as u128).to_be_bytes());
+            }
+        }
+    }
+}
+
+/// CTR mode state, encryption and decryption are the same operation.
+#[derive(Clone, Debug)]
+pub struct Ctr<C: BlockCipher> {
+    cipher: C,
+    initial: Vec<u8>,
+    layout: CounterLayout,
+    position: u64,
+}
+
+impl<C: BlockCipher> Ctr<C> {
+    /// `initial` is the counter block of the first keystream block.
+    pub fn new(cipher: C, initial: &[u8], layout: CounterLayout) -> Self {
+        assert_eq!(
+            initial.len(),
+            cipher.block_size(),
+            "Initial counter block must be of block size"
+        );
+        assert!(
+            cipher.block_size() >= layout.counter_len(),
+            "Counter doesn't fit into block"
+        );
+        Self {
+            cipher,
+            initial: initial.to_vec(),
+            layout,
+            position: 0,
+        }
+    }
+
+    /// Cryptopals layout with counter starting from zero, needs at least 128-bit blocks
+    /// to fit both the nonce and the counter.
+    pub fn cryptopals(cipher: C, nonce: u64) -> Self {
+        assert!(
+            cipher.block_size() >= 16,
+            "Cryptopals layout requires block size of at least 16 bytes"
+        );
+        let mut initial = vec![0; cipher.block_size()];
+        let nonce_end = initial.len() - CounterLayout::Cryptopals.counter_len();
+        initial[nonce_end - 8..nonce_end].copy_from_slice(&nonce.to_le_bytes());
+        Self::new(cipher, &initial, CounterLayout::Cryptopals)
+    }
+
+    /// Byte offset in the keystream.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn seek(&mut self, offset: u64) {
+        self.position = offset;
+    }
+
+    /// Counter block of the keystream block `index`.
+    pub fn counter_block(&self, index: u64) -> Vec<u8> {
+        let mut block = self.initial.clone();
+        self.layout.advance(&mut block, index);
+        block
+    }
+
+    /// XORs keystream from the current position into `data` and moves past it.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        let block_size = self.cipher.block_size() as u64;
+        let mut done = 0;
+        while done < data.len() {
+            let index = self.position / block_size;
+            let skip = (self.position % block_size) as usize;
+            let mut keystream = self.counter_block(index);
+            self.cipher.encrypt_block(&mut keystream);
+            let count = (keystream.len() - skip).min(data.len() - done);
+            data[done..done + count]
+                .iter_mut()
+                .zip(&keystream[skip..skip + count])
+                .for_each(|(d, k)| *d ^= k);
+            done += count;
+            self.position += count as u64;
+        }
+    }
+
+    /// Same as `apply_keystream`, but returns a new buffer.
+    pub fn process(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut output = data.to_vec();
+        self.apply_keystream(&mut output);
+        output
+    }
+
+    /// Keystream bytes at `offset`, position is not changed.
+    pub fn keystream_at(&self, offset: u64, len: usize) -> Vec<u8> {
+        let mut probe = Ctr {
+            cipher: &self.cipher,
+            initial: self.initial.clone(),
+            layout: self.layout,
+            position: offset,
+        };
+        probe.process(&vec![0; len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::Aes;
+    use crate::task_1::Base64Config;
+
+    #[test]
+    fn aes_ctr_should_pass() {
+        // NIST SP 800-38A, F.5.1
+        let aes = Aes::new(&hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap()).unwrap();
+        let initial = hex::decode("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff").unwrap();
+        let plaintext = hex::decode(
+            "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+             30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710",
+        )
+        .unwrap();
+        let expected = "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff\
+                        5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee";
+        for layout in [CounterLayout::Full128, CounterLayout::Nist] {
+            let mut ctr = Ctr::new(&aes, &initial, layout);
+            assert_eq!(hex::encode(ctr.process(&plaintext)), expected);
+            assert_eq!(ctr.position(), 64);
+        }
+
+        let cipher = Base64Config::STANDARD
+            .decode("L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==")
+            .unwrap();
+        let mut ctr = Ctr::cryptopals(Aes::new(b"YELLOW SUBMARINE").unwrap(), 0);
+        let plaintext = ctr.process(&cipher);
+        assert_eq!(
+            plaintext,
+            b"Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby "
+        );
+        ctr.seek(21);
+        assert_eq!(ctr.process(&cipher[21..]), &plaintext[21..]);
+        assert_eq!(
+            ctr.keystream_at(5, 30),
+            cipher[5..35]
+                .iter()
+                .zip(&plaintext[5..35])
+                .map(|(c, p)| c ^ p)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(ctr.position(), cipher.len() as u64);
+    }
+
+    #[test]
+    fn counter_layouts_should_pass() {
+        let aes = Aes::new(b"YELLOW SUBMARINE").unwrap();
+        let mut initial = vec![0xab; 12];
+        initial.extend_from_slice(&[0xff; 4]);
+        let nist = Ctr::new(&aes, &initial, CounterLayout::Nist);
+        assert_eq!(
+            nist.counter_block(2)[..],
+            [&[0xab; 12][..], &[0, 0, 0, 1]].concat()
+        );
+        let full = Ctr::new(&aes, &initial, CounterLayout::Full128);
+        assert_eq!(full.counter_block(1)[11..], [0xac, 0, 0, 0, 0]);
+        let cryptopals = Ctr::cryptopals(&aes, 0x0102);
+        assert_eq!(
+            cryptopals.counter_block(0x0304),
+            [2, 1, 0, 0, 0, 0, 0, 0, 4, 3, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cryptopals layout requires block size of at least 16 bytes")]
+    fn cryptopals_small_block_should_panic() {
+        /// 64-bit identity cipher.
+        struct Toy;
+
+        impl BlockCipher for Toy {
+            fn block_size(&self) -> usize {
+                8
+            }
+
+            fn encrypt_block(&self, _block: &mut [u8]) {}
+
+            fn decrypt_block(&self, _block: &mut [u8]) {}
+        }
+
+        Ctr::cryptopals(Toy, 0);
+    }
+}
@@ -0,0 +1,249 @@
+/// Distance metrics between byte strings: Hamming, edit distance and bit correlation
+const WORD: usize = std::mem::size_of::<u64>();
+
+/// Number of differing bits in the common prefix of `a` and `b`, compared word by word.
+pub fn count_differing_bits(a: &[u8], b: &[u8]) -> u32 {
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[..len], &b[..len]);
+    let words_a = a.chunks_exact(WORD);
+    let words_b = b.chunks_exact(WORD);
+    let tail = words_a
+        .remainder()
+        .iter()
+        .zip(words_b.remainder())
+        .map(|(&x, &y)| (x ^ y).count_ones())
+        .sum::<u32>();
+    words_a
+        .zip(words_b)
+        .map(|(x, y)| (read_word(x) ^ read_word(y)).count_ones())
+        .sum::<u32>()
+        + tail
+}
+
+/// Number of set bits, counted word by word.
+pub fn count_ones(data: &[u8]) -> u32 {
+    let words = data.chunks_exact(WORD);
+    let tail = words
+        .remainder()
+        .iter()
+        .map(|b| b.count_ones())
+        .sum::<u32>();
+    words.map(|w| read_word(w).count_ones()).sum::<u32>() + tail
+}
+
+fn read_word(bytes: &[u8]) -> u64 {
+    u64::from_ne_bytes(bytes.try_into().expect("chunk is a word long"))
+}
+
+/// How inputs of different lengths are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LengthPolicy {
+    /// Inputs of different lengths are not comparable.
+    #[default]
+    Exact,
+    /// Longer input is cut to the length of the shorter one.
+    Truncate,
+    /// Shorter input is extended with the given byte.
+    Pad(u8),
+}
+
+impl LengthPolicy {
+    /// Number of compared bytes, `None` if lengths are not comparable.
+    pub fn compared_len(&self, a: &[u8], b: &[u8]) -> Option<usize> {
+        match self {
+            LengthPolicy::Exact if a.len() != b.len() => None,
+            LengthPolicy::Exact | LengthPolicy::Truncate => Some(a.len().min(b.len())),
+            LengthPolicy::Pad(_) => Some(a.len().max(b.len())),
+        }
+    }
+}
+
+/// Common interface for distances, smaller is closer.
+pub trait Distance {
+    /// `None` when inputs can't be compared.
+    fn distance(&self, a: &[u8], b: &[u8]) -> Option<f64>;
+
+    /// Distance scaled by number of compared bytes, zero for empty inputs.
+    fn normalized(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        let len = a.len().max(b.len());
+        let dist = self.distance(a, b)?;
+        Some(if len == 0 { 0.0 } else { dist / len as f64 })
+    }
+}
+
+impl<D: Distance + ?Sized> Distance for &D {
+    fn distance(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        (**self).distance(a, b)
+    }
+
+    fn normalized(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        (**self).normalized(a, b)
+    }
+}
+
+/// Number of differing bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hamming {
+    pub policy: LengthPolicy,
+}
+
+impl Hamming {
+    pub const EXACT: Hamming = Hamming {
+        policy: LengthPolicy::Exact,
+    };
+    pub const TRUNCATE: Hamming = Hamming {
+        policy: LengthPolicy::Truncate,
+    };
+
+    pub fn bits(&self, a: &[u8], b: &[u8]) -> Option<u32> {
+        self.policy.compared_len(a, b)?;
+        let common = count_differing_bits(a, b);
+        let padding = match self.policy {
+            LengthPolicy::Pad(pad) => {
+                let (short, long) = if a.len() < b.len() { (a, b) } else { (b, a) };
+                long[short.len()..]
+                    .iter()
+                    .map(|&byte| (byte ^ pad).count_ones())
+                    .sum()
+            }
+            _ => 0,
+        };
+        Some(common + padding)
+    }
+}
+
+impl Distance for Hamming {
+    fn distance(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        self.bits(a, b).map(f64::from)
+    }
+
+    fn normalized(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        let len = self.policy.compared_len(a, b)?;
+        let dist = self.distance(a, b)?;
+        Some(if len == 0 { 0.0 } else { dist / len as f64 })
+    }
+}
+
+/// Levenshtein distance over bytes, every insertion, deletion or substitution costs one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EditDistance;
+
+impl EditDistance {
+    pub fn edits(&self, a: &[u8], b: &[u8]) -> usize {
+        let mut row = (0..=b.len()).collect::<Vec<_>>();
+        for (i, &x) in a.iter().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, &y) in b.iter().enumerate() {
+                let substitution = diagonal + usize::from(x != y);
+                diagonal = row[j + 1];
+                row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+            }
+        }
+        row[b.len()]
+    }
+}
+
+impl Distance for EditDistance {
+    fn distance(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        Some(self.edits(a, b) as f64)
+    }
+}
+
+/// Pearson correlation of bits, distance is `1 - correlation` in `0.0..=2.0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BitCorrelation {
+    pub policy: LengthPolicy,
+}
+
+impl BitCorrelation {
+    /// Correlation in `-1.0..=1.0`, zero when one of the inputs has constant bits.
+    pub fn correlation(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        let len = self.policy.compared_len(a, b)?;
+        let (a, b) = match self.policy {
+            LengthPolicy::Pad(pad) => (padded(a, len, pad), padded(b, len, pad)),
+            _ => (a[..len].to_vec(), b[..len].to_vec()),
+        };
+        let bits = (len * 8) as f64;
+        let ones_a = count_ones(&a) as f64;
+        let ones_b = count_ones(&b) as f64;
+        let both = a
+            .iter()
+            .zip(b.iter())
+            .map(|(x, y)| x & y)
+            .collect::<Vec<_>>();
+        let ones_both = count_ones(&both) as f64;
+        let spread = ones_a * (bits - ones_a) * ones_b * (bits - ones_b);
+        if spread == 0.0 {
+            return Some(0.0);
+        }
+        Some((bits * ones_both - ones_a * ones_b) / spread.sqrt())
+    }
+}
+
+fn padded(data: &[u8], len: usize, pad: u8) -> Vec<u8> {
+    let mut result = data.to_vec();
+    result.resize(len, pad);
+    result
+}
+
+impl Distance for BitCorrelation {
+    fn distance(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        self.correlation(a, b).map(|corr| 1.0 - corr)
+    }
+
+    /// Correlation distance doesn't depend on the length.
+    fn normalized(&self, a: &[u8], b: &[u8]) -> Option<f64> {
+        self.distance(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hamming_should_pass() {
+        let a = b"this is a test";
+        let b = b"wokka wokka!!!";
+        assert_eq!(count_differing_bits(a, b), 37);
+        assert_eq!(Hamming::EXACT.bits(a, b), Some(37));
+        assert_eq!(Hamming::EXACT.bits(b"abc", b"abcd"), None);
+        assert_eq!(Hamming::TRUNCATE.bits(b"abc", b"abcd"), Some(0));
+        assert_eq!(
+            Hamming::TRUNCATE.normalized(b"abb", b"abcd"),
+            Some(1.0 / 3.0)
+        );
+        let padded = Hamming {
+            policy: LengthPolicy::Pad(0),
+        };
+        assert_eq!(padded.bits(b"abc", b"abc\x0f\x01"), Some(5));
+        assert_eq!(padded.normalized(b"abc\x0f\x01", b"abc"), Some(1.0));
+        assert_eq!(count_ones(&[0xff; 19]), 152);
+    }
+
+    #[test]
+    fn edit_distance_should_pass() {
+        assert_eq!(EditDistance.edits(b"kitten", b"sitting"), 3);
+        assert_eq!(EditDistance.edits(b"", b"abc"), 3);
+        assert_eq!(EditDistance.edits(b"flaw", b"lawn"), 2);
+        assert_eq!(EditDistance.normalized(b"", b""), Some(0.0));
+        assert_eq!(EditDistance.normalized(b"abcd", b"abce"), Some(0.25));
+    }
+
+    #[test]
+    fn bit_correlation_should_pass() {
+        let corr = BitCorrelation::default();
+        let data = b"\x0f\xf0\x33\xa5";
+        let inverted = data.iter().map(|b| !b).collect::<Vec<_>>();
+        assert!((corr.correlation(data, data).unwrap() - 1.0).abs() < 1e-12);
+        assert!((corr.correlation(data, &inverted).unwrap() + 1.0).abs() < 1e-12);
+        assert_eq!(corr.correlation(b"\x00\x00", data), None);
+        assert_eq!(corr.correlation(b"\x00\x00", b"\x0f\xf0"), Some(0.0));
+        assert_eq!(corr.correlation(b"\x0f", b"\x33"), Some(0.0));
+        let truncated = BitCorrelation {
+            policy: LengthPolicy::Truncate,
+        };
+        assert!(truncated.distance(data, &data[..2]).unwrap().abs() < 1e-12);
+    }
+}
@@ -0,0 +1,292 @@
+/// Galois/Counter Mode authenticated encryption with pure-Rust GHASH
+use crate::block_cipher::BlockCipher;
+use crate::ctr::{CounterLayout, Ctr};
+use crate::task_7::AES_BLOCK_SIZE;
+
+pub type Tag = [u8; AES_BLOCK_SIZE];
+
+/// Longest plaintext in bytes, `2^39 - 256` bits. The 32-bit counter would
+/// wrap around and reuse keystream past it.
+pub const MAX_PLAINTEXT_LEN: u64 = (1 << 36) - 32;
+
+/// Reduction polynomial `x^128 + x^7 + x^2 + x + 1` in GCM bit order.
+const R: u128 = 0xe1 << 120;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GcmError {
+    /// Tag doesn't match ciphertext and associated data.
+    AuthenticationFailed,
+    /// Ciphertext is longer than [`MAX_PLAINTEXT_LEN`].
+    MessageTooLong,
+}
+
+impl std::fmt::Display for GcmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GcmError::AuthenticationFailed => write!(f, "authentication tag mismatch"),
+            GcmError::MessageTooLong => write!(f, "message exceeds GCM length limit"),
+        }
+    }
+}
+
+impl std::error::Error for GcmError {}
+
+/// Multiplication in GF(2^128), the most significant bit holds the coefficient of `x^0`.
+pub fn gf128_mul(x: u128, y: u128) -> u128 {
+    let mut product = 0;
+    let mut v = y;
+    for bit in (0..128).rev() {
+        if (x >> bit) & 1 == 1 {
+            product ^= v;
+        }
+        v = if v & 1 == 1 { (v >> 1) ^ R } else { v >> 1 };
+    }
+    product
+}
+
+/// Big-endian blocks of `data`, the last one is padded with zeros.
+pub fn to_blocks(data: &[u8]) -> impl Iterator<Item = u128> + '_ {
+    data.chunks(AES_BLOCK_SIZE).map(|chunk| {
+        let mut block = [0; AES_BLOCK_SIZE];
+        block[..chunk.len()].copy_from_slice(chunk);
+        u128::from_be_bytes(block)
+    })
+}
+
+/// Block with bit lengths of associated data and ciphertext.
+pub fn length_block(aad_len: usize, ciphertext_len: usize) -> u128 {
+    ((aad_len as u128 * 8) << 64) | (ciphertext_len as u128 * 8)
+}
+
+pub fn ghash(h: u128, aad: &[u8], ciphertext: &[u8]) -> u128 {
+    to_blocks(aad)
+        .chain(to_blocks(ciphertext))
+        .chain(std::iter::once(length_block(aad.len(), ciphertext.len())))
+        .fold(0, |acc, block| gf128_mul(acc ^ block, h))
+}
+
+/// Compares tags without early exit.
+pub fn tags_equal(a: &[u8], b: &[u8]) -> bool {
+    let diff = a
+        .iter()
+        .zip(b)
+        .fold((a.len() != b.len()) as u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+/// GCM over a 128-bit block cipher, tags are 128 bits long.
+#[derive(Clone, Debug)]
+pub struct Gcm<C: BlockCipher> {
+    cipher: C,
+    h: u128,
+}
+
+impl<C: BlockCipher> Gcm<C> {
+    pub fn new(cipher: C) -> Self {
+        assert_eq!(
+            cipher.block_size(),
+            AES_BLOCK_SIZE,
+            "GCM requires 128-bit block cipher"
+        );
+        let mut h = [0; AES_BLOCK_SIZE];
+        cipher.encrypt_block(&mut h);
+        Self {
+            cipher,
+            h: u128::from_be_bytes(h),
+        }
+    }
+
+    /// Authentication key, encryption of the zero block.
+    pub fn h(&self) -> u128 {
+        self.h
+    }
+
+    /// Pre-counter block, 96-bit IVs are used directly, others are hashed.
+    pub fn j0(&self, iv: &[u8]) -> [u8; AES_BLOCK_SIZE] {
+        assert!(!iv.is_empty(), "IV must not be empty");
+        if iv.len() == 12 {
+            let mut j0 = [0; AES_BLOCK_SIZE];
+            j0[..12].copy_from_slice(iv);
+            j0[15] = 1;
+            j0
+        } else {
+            ghash(self.h, &[], iv).to_be_bytes()
+        }
+    }
+
+    /// Keystream starts from the counter following `j0`.
+    fn keystream(&self, j0: &[u8; AES_BLOCK_SIZE]) -> Ctr<&C> {
+        let mut ctr = Ctr::new(&self.cipher, j0, CounterLayout::Nist);
+        ctr.seek(AES_BLOCK_SIZE as u64);
+        ctr
+    }
+
+    /// Tag of the ciphertext, available for nonce-misuse experiments.
+    pub fn tag(&self, iv: &[u8], aad: &[u8], ciphertext: &[u8]) -> Tag {
+        let mut mask = self.j0(iv);
+        self.cipher.encrypt_block(&mut mask);
+        (ghash(self.h, aad, ciphertext) ^ u128::from_be_bytes(mask)).to_be_bytes()
+    }
+
+    /// Plaintext must not exceed [`MAX_PLAINTEXT_LEN`].
+    pub fn seal(&self, iv: &[u8], aad: &[u8], plaintext: &[u8]) -> (Vec<u8>, Tag) {
+        assert!(
+            plaintext.len() as u64 <= MAX_PLAINTEXT_LEN,
+            "Plaintext exceeds GCM length limit"
+        );
+        let ciphertext = self.keystream(&self.j0(iv)).process(plaintext);
+        let tag = self.tag(iv, aad, &ciphertext);
+        (ciphertext, tag)
+    }
+
+    /// Plaintext is released only after the tag is verified.
+    pub fn open(
+        &self,
+        iv: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+        tag: &[u8],
+    ) -> Result<Vec<u8>, GcmError> {
+        if ciphertext.len() as u64 > MAX_PLAINTEXT_LEN {
+            return Err(GcmError::MessageTooLong);
+        }
+        if !tags_equal(&self.tag(iv, aad, ciphertext), tag) {
+            return Err(GcmError::AuthenticationFailed);
+        }
+        Ok(self.keystream(&self.j0(iv)).process(ciphertext))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::Aes;
+
+    fn unhex(hex_str: &str) -> Vec<u8> {
+        hex::decode(hex_str).unwrap()
+    }
+
+    #[test]
+    fn gcm_vectors_should_pass() {
+        // test cases 1 to 6 of the GCM specification
+        let key = unhex("feffe9928665731c6d6a8f9467308308");
+        let plaintext = unhex(
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+             1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+        );
+        let aad = unhex("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+        let cases = [
+            (
+                "cafebabefacedbaddecaf888",
+                "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+                 21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+                "5bc94fbc3221a5db94fae95ae7121a47",
+            ),
+            (
+                "cafebabefacedbad",
+                "61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c7423\
+                 73806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598",
+                "3612d2e79e3b0785561be14aaca2fccb",
+            ),
+            (
+                "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728\
+                 c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+                "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca7\
+                 01e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5",
+                "619cc5aefffe0bfa462af43c1699d050",
+            ),
+        ];
+        let gcm = Gcm::new(Aes::new(&key).unwrap());
+        for (iv, expected_cipher, expected_tag) in cases {
+            let iv = unhex(iv);
+            let (ciphertext, tag) = gcm.seal(&iv, &aad, &plaintext);
+            assert_eq!(hex::encode(&ciphertext), expected_cipher);
+            assert_eq!(hex::encode(tag), expected_tag);
+            assert_eq!(
+                gcm.open(&iv, &aad, &ciphertext, &tag),
+                Ok(plaintext.clone())
+            );
+        }
+
+        // test case 3, no associated data
+        let plaintext = unhex(
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+             1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+        );
+        let iv = unhex("cafebabefacedbaddecaf888");
+        let (ciphertext, tag) = gcm.seal(&iv, &[], &plaintext);
+        assert_eq!(
+            hex::encode(&ciphertext),
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+             21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985"
+        );
+        assert_eq!(hex::encode(tag), "4d5c2af327cd64a62cf35abd2ba6fab4");
+        assert_eq!(gcm.open(&iv, &[], &ciphertext, &tag), Ok(plaintext));
+
+        let gcm = Gcm::new(Aes::new(&[0; 16]).unwrap());
+        let (ciphertext, tag) = gcm.seal(&[0; 12], &[], &[]);
+        assert!(ciphertext.is_empty());
+        assert_eq!(hex::encode(tag), "58e2fccefa7e3061367f1d57a4e7455a");
+        let (ciphertext, tag) = gcm.seal(&[0; 12], &[], &[0; 16]);
+        assert_eq!(hex::encode(ciphertext), "0388dace60b6a392f328c2b971b2fe78");
+        assert_eq!(hex::encode(tag), "ab6e47d42cec13bdf53a67b21257bddf");
+        assert_eq!(
+            hex::encode(gcm.h().to_be_bytes()),
+            "66e94bd4ef8a2c3b884cfa59ca342b2e"
+        );
+    }
+
+    #[test]
+    fn gcm_tampering_should_pass() {
+        let gcm = Gcm::new(Aes::new(b"YELLOW SUBMARINE").unwrap());
+        let iv = b"unique nonce";
+        let (mut ciphertext, tag) = gcm.seal(iv, b"header", b"attack at dawn");
+        assert_eq!(
+            gcm.open(iv, b"header", &ciphertext, &tag[..15]),
+            Err(GcmError::AuthenticationFailed)
+        );
+        assert_eq!(
+            gcm.open(iv, b"headers", &ciphertext, &tag),
+            Err(GcmError::AuthenticationFailed)
+        );
+        ciphertext[0] ^= 1;
+        assert_eq!(
+            gcm.open(iv, b"header", &ciphertext, &tag),
+            Err(GcmError::AuthenticationFailed)
+        );
+        // multiplication is commutative and `x^0` is the identity
+        let (a, b) = (0x0123456789abcdef_u128 << 17, 0xfedcba9876543210_u128);
+        assert_eq!(gf128_mul(a, b), gf128_mul(b, a));
+        assert_eq!(gf128_mul(a, 1 << 127), a);
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn openssl_gcm_should_pass() {
+        use openssl::symm::{encrypt_aead, Cipher};
+
+        let key = b"YELLOW SUBMARINE";
+        let gcm = Gcm::new(Aes::new(key).unwrap());
+        for (iv, aad, plaintext) in [
+            (
+                &b"twelve bytes"[..],
+                &b""[..],
+                &b"Cooking MC's like a pound of bacon"[..],
+            ),
+            (b"short", b"associated data", b"ICE ICE BABY"),
+            (b"a nonce which is longer than a block", b"aad", b""),
+        ] {
+            let mut expected_tag = [0; AES_BLOCK_SIZE];
+            let expected = encrypt_aead(
+                Cipher::aes_128_gcm(),
+                key,
+                Some(iv),
+                aad,
+                plaintext,
+                &mut expected_tag,
+            )
+            .unwrap();
+            assert_eq!(gcm.seal(iv, aad, plaintext), (expected, expected_tag));
+        }
+    }
+}
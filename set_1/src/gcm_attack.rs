@@ -0,0 +1,474 @@
+/// GCM nonce-reuse "forbidden attack": authentication key recovery and tag forgery
+use crate::gcm::{gf128_mul, ghash, length_block, to_blocks, Tag};
+use rand::Rng;
+
+/// Multiplicative identity in GCM bit order.
+pub const GF_ONE: u128 = 1 << 127;
+/// Number of squarings which give the Frobenius map `a -> a^q` for `q = 2^128`.
+const FIELD_BITS: usize = 128;
+
+pub fn gf128_pow(mut base: u128, mut exp: u128) -> u128 {
+    let mut result = GF_ONE;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf128_mul(result, base);
+        }
+        base = gf128_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Inverse as `a^(2^128 - 2)`, `a` must not be zero.
+pub fn gf128_inverse(a: u128) -> u128 {
+    assert_ne!(a, 0, "Zero has no inverse");
+    gf128_pow(a, u128::MAX - 1)
+}
+
+/// Square root as `a^(2^127)`, squaring is a bijection in characteristic 2.
+pub fn gf128_sqrt(a: u128) -> u128 {
+    (1..FIELD_BITS).fold(a, |x, _| gf128_mul(x, x))
+}
+
+/// Polynomial over GF(2^128), `coeffs[i]` is the coefficient of `X^i`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GfPoly {
+    /// No trailing zeros, empty for the zero polynomial.
+    coeffs: Vec<u128>,
+}
+
+impl GfPoly {
+    pub fn new(mut coeffs: Vec<u128>) -> Self {
+        while coeffs.last() == Some(&0) {
+            coeffs.pop();
+        }
+        Self { coeffs }
+    }
+
+    pub fn zero() -> Self {
+        Self { coeffs: vec![] }
+    }
+
+    pub fn one() -> Self {
+        Self {
+            coeffs: vec![GF_ONE],
+        }
+    }
+
+    /// Monic `X + root`, subtraction is addition in characteristic 2.
+    pub fn linear(root: u128) -> Self {
+        Self {
+            coeffs: vec![root, GF_ONE],
+        }
+    }
+
+    pub fn coeffs(&self) -> &[u128] {
+        &self.coeffs
+    }
+
+    /// `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.coeffs.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.coeffs == [GF_ONE]
+    }
+
+    pub fn eval(&self, x: u128) -> u128 {
+        self.coeffs
+            .iter()
+            .rev()
+            .fold(0, |acc, &c| gf128_mul(acc, x) ^ c)
+    }
+
+    pub fn add(&self, other: &GfPoly) -> GfPoly {
+        let len = self.coeffs.len().max(other.coeffs.len());
+        GfPoly::new(
+            (0..len)
+                .map(|i| self.coeffs.get(i).unwrap_or(&0) ^ other.coeffs.get(i).unwrap_or(&0))
+                .collect(),
+        )
+    }
+
+    pub fn mul(&self, other: &GfPoly) -> GfPoly {
+        if self.is_zero() || other.is_zero() {
+            return GfPoly::zero();
+        }
+        let mut coeffs = vec![0; self.coeffs.len() + other.coeffs.len() - 1];
+        for (i, &a) in self.coeffs.iter().enumerate() {
+            for (j, &b) in other.coeffs.iter().enumerate() {
+                coeffs[i + j] ^= gf128_mul(a, b);
+            }
+        }
+        GfPoly::new(coeffs)
+    }
+
+    pub fn scale(&self, factor: u128) -> GfPoly {
+        GfPoly::new(self.coeffs.iter().map(|&c| gf128_mul(c, factor)).collect())
+    }
+
+    /// Scaled to the leading coefficient one, zero stays zero.
+    pub fn monic(&self) -> GfPoly {
+        match self.coeffs.last() {
+            Some(&lead) => self.scale(gf128_inverse(lead)),
+            None => GfPoly::zero(),
+        }
+    }
+
+    /// Quotient and remainder, `divisor` must not be zero.
+    pub fn div_rem(&self, divisor: &GfPoly) -> (GfPoly, GfPoly) {
+        let divisor_degree = divisor.degree().expect("Division by zero polynomial");
+        let lead_inverse = gf128_inverse(divisor.coeffs[divisor_degree]);
+        let mut rem = self.coeffs.clone();
+        let quotient_len = (rem.len() + 1).saturating_sub(divisor.coeffs.len());
+        let mut quotient = vec![0; quotient_len];
+        for shift in (0..quotient_len).rev() {
+            let factor = gf128_mul(rem[shift + divisor_degree], lead_inverse);
+            quotient[shift] = factor;
+            for (i, &d) in divisor.coeffs.iter().enumerate() {
+                rem[shift + i] ^= gf128_mul(d, factor);
+            }
+        }
+        rem.truncate(divisor_degree);
+        (GfPoly::new(quotient), GfPoly::new(rem))
+    }
+
+    pub fn rem(&self, divisor: &GfPoly) -> GfPoly {
+        self.div_rem(divisor).1
+    }
+
+    /// Exact division, the remainder is discarded.
+    pub fn div(&self, divisor: &GfPoly) -> GfPoly {
+        self.div_rem(divisor).0
+    }
+
+    /// Monic greatest common divisor.
+    pub fn gcd(&self, other: &GfPoly) -> GfPoly {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let rem = a.rem(&b);
+            a = b;
+            b = rem;
+        }
+        a.monic()
+    }
+
+    /// Formal derivative, in characteristic 2 only odd powers survive.
+    pub fn derivative(&self) -> GfPoly {
+        GfPoly::new(
+            self.coeffs
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+                .collect(),
+        )
+    }
+
+    /// Square root of a polynomial with zero derivative, i.e. of a perfect square.
+    pub fn sqrt(&self) -> GfPoly {
+        GfPoly::new(
+            self.coeffs
+                .iter()
+                .step_by(2)
+                .map(|&c| gf128_sqrt(c))
+                .collect(),
+        )
+    }
+
+    /// `self^(2^k) mod modulus` by repeated squaring.
+    pub fn pow2k_mod(&self, k: usize, modulus: &GfPoly) -> GfPoly {
+        (0..k).fold(self.rem(modulus), |acc, _| acc.mul(&acc).rem(modulus))
+    }
+}
+
+/// Factors of a monic polynomial into square-free parts with their multiplicities.
+pub fn square_free_factorization(f: &GfPoly) -> Vec<(GfPoly, usize)> {
+    if f.degree().unwrap_or(0) == 0 {
+        return vec![];
+    }
+    let derivative = f.derivative();
+    if derivative.is_zero() {
+        return square_free_factorization(&f.sqrt())
+            .into_iter()
+            .map(|(g, m)| (g, 2 * m))
+            .collect();
+    }
+    let mut result = Vec::new();
+    let mut c = f.gcd(&derivative);
+    let mut w = f.div(&c);
+    let mut multiplicity = 1;
+    while !w.is_one() {
+        let y = w.gcd(&c);
+        let factor = w.div(&y);
+        if !factor.is_one() {
+            result.push((factor.monic(), multiplicity));
+        }
+        multiplicity += 1;
+        w = y;
+        c = c.div(&w);
+    }
+    if !c.is_one() {
+        // remaining factors have multiplicities divisible by 2
+        result.extend(
+            square_free_factorization(&c.monic().sqrt())
+                .into_iter()
+                .map(|(g, m)| (g, 2 * m)),
+        );
+    }
+    result
+}
+
+/// Splits a monic square-free polynomial into products of irreducible factors
+/// of equal degree, returns pairs of product and factor degree.
+pub fn distinct_degree_factorization(f: &GfPoly) -> Vec<(GfPoly, usize)> {
+    let mut result = Vec::new();
+    let mut rest = f.monic();
+    let x = GfPoly::new(vec![0, GF_ONE]);
+    // X^(q^degree) mod rest
+    let mut frobenius = x.clone();
+    let mut degree = 1;
+    while rest.degree().unwrap_or(0) >= 2 * degree {
+        frobenius = frobenius.pow2k_mod(FIELD_BITS, &rest);
+        let g = rest.gcd(&frobenius.add(&x));
+        if !g.is_one() {
+            rest = rest.div(&g);
+            frobenius = frobenius.rem(&rest);
+            result.push((g, degree));
+        }
+        degree += 1;
+    }
+    if let Some(rest_degree) = rest.degree().filter(|&d| d > 0) {
+        result.push((rest, rest_degree));
+    }
+    result
+}
+
+/// Cantor–Zassenhaus splitting of a monic product of distinct irreducible factors
+/// of degree `degree`, the trace map is used since the characteristic is 2.
+pub fn equal_degree_factorization<R: Rng>(f: &GfPoly, degree: usize, rng: &mut R) -> Vec<GfPoly> {
+    let n = f.degree().expect("Polynomial must not be zero");
+    if n <= degree {
+        return vec![f.monic()];
+    }
+    loop {
+        let a = GfPoly::new((0..n).map(|_| rng.gen()).collect());
+        let mut term = a.rem(f);
+        let mut trace = term.clone();
+        for _ in 1..FIELD_BITS * degree {
+            term = term.mul(&term).rem(f);
+            trace = trace.add(&term);
+        }
+        let g = f.gcd(&trace);
+        if !g.is_one() && g.degree() != f.degree() {
+            let mut factors = equal_degree_factorization(&g, degree, rng);
+            factors.extend(equal_degree_factorization(&f.div(&g), degree, rng));
+            return factors;
+        }
+    }
+}
+
+/// Product of all linear factors of a monic square-free polynomial,
+/// `gcd(f, X^(2^128) + X)`.
+pub fn linear_part(f: &GfPoly) -> GfPoly {
+    let x = GfPoly::linear(0);
+    f.gcd(&x.pow2k_mod(FIELD_BITS, f).add(&x))
+}
+
+/// Distinct roots in GF(2^128), sorted.
+pub fn find_roots(f: &GfPoly) -> Vec<u128> {
+    let mut rng = rand::thread_rng();
+    let mut roots = square_free_factorization(&f.monic())
+        .iter()
+        .map(|(square_free, _)| linear_part(square_free))
+        .filter(|linear| !linear.is_one())
+        .flat_map(|linear| equal_degree_factorization(&linear, 1, &mut rng))
+        .map(|factor| factor.coeffs()[0])
+        .collect::<Vec<_>>();
+    roots.sort_unstable();
+    roots.dedup();
+    roots
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SealedMessage {
+    pub aad: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub tag: Tag,
+}
+
+/// Polynomial in the authentication key `H` whose value is `tag` XOR-ed with
+/// the tag mask `E(J0)`, i.e. it's zero at `H` for the correct mask.
+pub fn ghash_polynomial(message: &SealedMessage) -> GfPoly {
+    let mut blocks = to_blocks(&message.aad)
+        .chain(to_blocks(&message.ciphertext))
+        .chain(std::iter::once(length_block(
+            message.aad.len(),
+            message.ciphertext.len(),
+        )))
+        .collect::<Vec<_>>();
+    blocks.push(u128::from_be_bytes(message.tag));
+    blocks.reverse();
+    GfPoly::new(blocks)
+}
+
+/// Candidates of `H` for messages sealed under the same key and nonce. Differences
+/// of GHASH polynomials cancel the tag mask and share `H` as a root, more messages
+/// leave fewer candidates.
+pub fn recover_auth_keys(messages: &[SealedMessage]) -> Vec<u128> {
+    assert!(messages.len() >= 2, "At least two messages are required");
+    let first = ghash_polynomial(&messages[0]);
+    let common = messages[1..]
+        .iter()
+        .map(|message| first.add(&ghash_polynomial(message)))
+        .filter(|diff| !diff.is_zero())
+        .reduce(|acc, diff| acc.gcd(&diff));
+    common.map_or_else(Vec::new, |common| find_roots(&common))
+}
+
+/// Recovered authentication key with the tag mask of the reused nonce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Forgery {
+    pub h: u128,
+    pub mask: u128,
+}
+
+impl Forgery {
+    pub fn new(h: u128, message: &SealedMessage) -> Self {
+        let mask = u128::from_be_bytes(message.tag) ^ ghash(h, &message.aad, &message.ciphertext);
+        Self { h, mask }
+    }
+
+    /// Valid tag for any ciphertext under the reused nonce.
+    pub fn forge(&self, aad: &[u8], ciphertext: &[u8]) -> Tag {
+        (ghash(self.h, aad, ciphertext) ^ self.mask).to_be_bytes()
+    }
+}
+
+/// Forgeries consistent with all messages.
+pub fn forbidden_attack(messages: &[SealedMessage]) -> Vec<Forgery> {
+    recover_auth_keys(messages)
+        .into_iter()
+        .map(|h| Forgery::new(h, &messages[0]))
+        .filter(|forgery| {
+            messages
+                .iter()
+                .all(|m| forgery.forge(&m.aad, &m.ciphertext) == m.tag)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::Aes;
+    use crate::gcm::Gcm;
+
+    /// Absolute trace, `X^2 + X + a` is irreducible exactly when it is one.
+    fn trace(a: u128) -> u128 {
+        (1..FIELD_BITS)
+            .scan(a, |power, _| {
+                *power = gf128_mul(*power, *power);
+                Some(*power)
+            })
+            .fold(a, |acc, power| acc ^ power)
+    }
+
+    #[test]
+    fn polynomial_factorization_should_pass() {
+        let mut rng = rand::thread_rng();
+        let a = std::iter::repeat_with(|| rng.gen::<u128>())
+            .find(|&a| trace(a) == GF_ONE)
+            .unwrap();
+        let (b, c) = (rng.gen::<u128>(), rng.gen::<u128>());
+        assert_eq!(gf128_mul(a, gf128_inverse(a)), GF_ONE);
+        assert_eq!(gf128_sqrt(gf128_mul(a, a)), a);
+        let quadratic = GfPoly::new(vec![a, GF_ONE, GF_ONE]);
+        let f = GfPoly::linear(a)
+            .mul(&GfPoly::linear(b).mul(&GfPoly::linear(b)))
+            .mul(
+                &GfPoly::linear(c)
+                    .mul(&GfPoly::linear(c))
+                    .mul(&GfPoly::linear(c)),
+            )
+            .mul(&quadratic);
+        let (quotient, rem) = f.div_rem(&quadratic);
+        assert!(rem.is_zero());
+        assert_eq!(quotient.mul(&quadratic), f);
+
+        let mut square_free = square_free_factorization(&f);
+        square_free.sort_by_key(|(_, multiplicity)| *multiplicity);
+        let simple = GfPoly::linear(a).mul(&quadratic);
+        assert_eq!(
+            square_free,
+            vec![
+                (simple.clone(), 1),
+                (GfPoly::linear(b), 2),
+                (GfPoly::linear(c), 3)
+            ]
+        );
+        assert_eq!(
+            distinct_degree_factorization(&simple),
+            vec![(GfPoly::linear(a), 1), (quadratic.clone(), 2)]
+        );
+        assert_eq!(linear_part(&simple), GfPoly::linear(a));
+        assert!(linear_part(&quadratic).is_one());
+        let product = GfPoly::linear(a)
+            .mul(&GfPoly::linear(b))
+            .mul(&GfPoly::linear(c));
+        let mut factors = equal_degree_factorization(&product, 1, &mut rng);
+        factors.sort_by_key(|factor| factor.coeffs()[0]);
+        let mut expected = vec![a, b, c];
+        expected.sort_unstable();
+        assert_eq!(
+            factors,
+            expected
+                .iter()
+                .map(|&r| GfPoly::linear(r))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(find_roots(&f), expected);
+        assert!(expected.iter().all(|&root| f.eval(root) == 0));
+    }
+
+    #[test]
+    fn forbidden_attack_should_pass() {
+        let gcm = Gcm::new(Aes::new(b"YELLOW SUBMARINE").unwrap());
+        let iv = b"reused nonce";
+        let seal = |aad: &[u8], plaintext: &[u8]| {
+            let (ciphertext, tag) = gcm.seal(iv, aad, plaintext);
+            SealedMessage {
+                aad: aad.to_vec(),
+                ciphertext,
+                tag,
+            }
+        };
+        let messages = [
+            seal(b"user=alice", b"transfer 100 to bob, thanks!"),
+            seal(b"user=carol", b"transfer 5 to dave"),
+            seal(b"", b"ICE ICE BABY"),
+        ];
+        let keys = recover_auth_keys(&messages[..2]);
+        assert!(keys.contains(&gcm.h()));
+        let forgeries = forbidden_attack(&messages);
+        assert_eq!(forgeries.len(), 1);
+        assert_eq!(forgeries[0].h, gcm.h());
+
+        // CTR is malleable, flip plaintext bits and forge a matching tag
+        let mut forged = messages[0].ciphertext.clone();
+        forged[9..12]
+            .iter_mut()
+            .zip(b"100".iter().zip(b"999"))
+            .for_each(|(c, (old, new))| *c ^= old ^ new);
+        let tag = forgeries[0].forge(b"user=admin", &forged);
+        assert_eq!(
+            gcm.open(iv, b"user=admin", &forged, &tag).unwrap(),
+            b"transfer 999 to bob, thanks!"
+        );
+    }
+}
@@ -0,0 +1,336 @@
+/// Block-aligned hexdump of ciphertexts with highlighting of repeated blocks
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// ANSI colours assigned to repeated blocks in order of their first occurrence.
+const PALETTE: [&str; 6] = [
+    "\x1b[31m", "\x1b[32m", "\x1b[33m", "\x1b[34m", "\x1b[35m", "\x1b[36m",
+];
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexdumpOptions {
+    pub block_size: usize,
+    /// Colour repeated blocks with ANSI escape codes.
+    pub color: bool,
+    /// Append printable ASCII representation of every block.
+    pub ascii: bool,
+}
+
+impl Default for HexdumpOptions {
+    fn default() -> Self {
+        Self {
+            block_size: 16,
+            color: false,
+            ascii: true,
+        }
+    }
+}
+
+/// Block which occurs more than once, `positions` are block indices in ascending order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockRepeat {
+    pub label: String,
+    pub block: Vec<u8>,
+    pub positions: Vec<usize>,
+}
+
+/// Spreadsheet-like labels: A..Z, AA, AB, ...
+pub fn block_label(mut idx: usize) -> String {
+    let mut label = Vec::new();
+    loop {
+        label.push(b'A' + (idx % 26) as u8);
+        if idx < 26 {
+            break;
+        }
+        idx = idx / 26 - 1;
+    }
+    label.into_iter().rev().map(|b| b as char).collect()
+}
+
+/// Finds all repeated full blocks, labelled in order of their first occurrence,
+/// trailing partial block is ignored.
+pub fn find_block_repeats<T: AsRef<[u8]>>(data: T, block_size: usize) -> Vec<BlockRepeat> {
+    assert!(block_size > 0, "Block size must be positive");
+    let mut positions = HashMap::<&[u8], Vec<usize>>::new();
+    let mut order = Vec::new();
+    for (idx, block) in data.as_ref().chunks_exact(block_size).enumerate() {
+        let entry = positions.entry(block).or_default();
+        if entry.is_empty() {
+            order.push(block);
+        }
+        entry.push(idx);
+    }
+    order
+        .into_iter()
+        .filter_map(|block| {
+            let block_positions = positions.remove(block)?;
+            (block_positions.len() > 1).then_some((block, block_positions))
+        })
+        .enumerate()
+        .map(|(idx, (block, positions))| BlockRepeat {
+            label: block_label(idx),
+            block: block.to_vec(),
+            positions,
+        })
+        .collect()
+}
+
+/// Maps block index to index of its entry in `repeats`.
+fn repeat_index(repeats: &[BlockRepeat]) -> HashMap<usize, usize> {
+    repeats
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, repeat)| repeat.positions.iter().map(move |&pos| (pos, idx)))
+        .collect()
+}
+
+fn write_hex(out: &mut String, block: &[u8], block_size: usize) {
+    for (i, b) in block.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        write!(out, "{:02x}", b).expect("writing to string");
+    }
+    // align partial blocks
+    for i in block.len()..block_size {
+        if i > 0 {
+            out.push(' ');
+        }
+        out.push_str("  ");
+    }
+}
+
+fn write_ascii(out: &mut String, block: &[u8], block_size: usize) {
+    out.push_str(" |");
+    out.extend(block.iter().map(|&b| match b {
+        0x20..=0x7e => b as char,
+        _ => '.',
+    }));
+    out.extend((block.len()..block_size).map(|_| ' '));
+    out.push('|');
+}
+
+/// Renders one line per block: offset, hex bytes, optional ASCII and label of repeated block.
+pub fn render_blocks<T: AsRef<[u8]>>(data: T, options: &HexdumpOptions) -> String {
+    let data_ref = data.as_ref();
+    let repeats = find_block_repeats(data_ref, options.block_size);
+    let labels = repeat_index(&repeats);
+    let mut out = String::new();
+    for (idx, block) in data_ref.chunks(options.block_size).enumerate() {
+        write!(out, "{:08x}  ", idx * options.block_size).expect("writing to string");
+        let repeat = labels.get(&idx);
+        if let (Some(&r), true) = (repeat, options.color) {
+            out.push_str(PALETTE[r % PALETTE.len()]);
+        }
+        write_hex(&mut out, block, options.block_size);
+        if repeat.is_some() && options.color {
+            out.push_str(RESET);
+        }
+        if options.ascii {
+            write_ascii(&mut out, block, options.block_size);
+        }
+        if let Some(&r) = repeat {
+            write!(out, "  [{}]", repeats[r].label).expect("writing to string");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Renders repeats one per line as tab-separated `label`, hex block and comma-separated positions.
+pub fn render_repeat_list(repeats: &[BlockRepeat]) -> String {
+    let mut out = String::new();
+    for repeat in repeats {
+        let positions = repeat
+            .positions
+            .iter()
+            .map(|pos| pos.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&repeat.label);
+        out.push('\t');
+        repeat
+            .block
+            .iter()
+            .for_each(|b| write!(out, "{:02x}", b).expect("writing to string"));
+        writeln!(out, "\t{}", positions).expect("writing to string");
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockDiff {
+    Same,
+    Changed,
+    /// Block exists only in the left ciphertext.
+    Removed,
+    /// Block exists only in the right ciphertext.
+    Added,
+}
+
+/// Compares ciphertexts block by block at the same positions.
+pub fn diff_blocks<T: AsRef<[u8]>>(left: T, right: T, block_size: usize) -> Vec<BlockDiff> {
+    assert!(block_size > 0, "Block size must be positive");
+    let left_blocks = left.as_ref().chunks(block_size).collect::<Vec<_>>();
+    let right_blocks = right.as_ref().chunks(block_size).collect::<Vec<_>>();
+    (0..left_blocks.len().max(right_blocks.len()))
+        .map(|idx| match (left_blocks.get(idx), right_blocks.get(idx)) {
+            (Some(l), Some(r)) if l == r => BlockDiff::Same,
+            (Some(_), Some(_)) => BlockDiff::Changed,
+            (Some(_), None) => BlockDiff::Removed,
+            (None, _) => BlockDiff::Added,
+        })
+        .collect()
+}
+
+/// Renders both ciphertexts side by side with a marker between them:
+/// `=` same, `!` changed, `<` only left, `>` only right.
+pub fn render_block_diff<T: AsRef<[u8]>>(left: T, right: T, options: &HexdumpOptions) -> String {
+    let (left_ref, right_ref) = (left.as_ref(), right.as_ref());
+    let block_size = options.block_size;
+    let (mut left_blocks, mut right_blocks) =
+        (left_ref.chunks(block_size), right_ref.chunks(block_size));
+    let mut out = String::new();
+    for (idx, diff) in diff_blocks(left_ref, right_ref, block_size)
+        .into_iter()
+        .enumerate()
+    {
+        let blocks = [
+            left_blocks.next().unwrap_or_default(),
+            right_blocks.next().unwrap_or_default(),
+        ];
+        let (marker, color) = match diff {
+            BlockDiff::Same => ('=', None),
+            BlockDiff::Changed => ('!', Some(PALETTE[0])),
+            BlockDiff::Removed => ('<', Some(PALETTE[0])),
+            BlockDiff::Added => ('>', Some(PALETTE[1])),
+        };
+        let color = color.filter(|_| options.color);
+        write!(out, "{:08x}  ", idx * block_size).expect("writing to string");
+        for (side, block) in blocks.into_iter().enumerate() {
+            if side == 1 {
+                write!(out, "  {}  ", marker).expect("writing to string");
+            }
+            if let Some(c) = color {
+                out.push_str(c);
+            }
+            write_hex(&mut out, block, block_size);
+            if color.is_some() {
+                out.push_str(RESET);
+            }
+            if options.ascii {
+                write_ascii(&mut out, block, block_size);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::load_hex_lines;
+
+    #[test]
+    fn block_labels_should_pass() {
+        assert_eq!(block_label(0), "A");
+        assert_eq!(block_label(25), "Z");
+        assert_eq!(block_label(26), "AA");
+        assert_eq!(block_label(27), "AB");
+        assert_eq!(block_label(26 + 26 * 26), "AAA");
+    }
+
+    #[test]
+    fn find_block_repeats_should_pass() {
+        let data = b"AAAABBBBAAAACCCCBBBBAAAADD";
+        let repeats = find_block_repeats(data, 4);
+        assert_eq!(
+            repeats,
+            vec![
+                BlockRepeat {
+                    label: "A".to_owned(),
+                    block: b"AAAA".to_vec(),
+                    positions: vec![0, 2, 5],
+                },
+                BlockRepeat {
+                    label: "B".to_owned(),
+                    block: b"BBBB".to_vec(),
+                    positions: vec![1, 4],
+                },
+            ]
+        );
+
+        let records = load_hex_lines("res/task8.txt").expect("Failed to load hex file.");
+        let ecb_repeats = records
+            .iter()
+            .map(|record| find_block_repeats(record, 16))
+            .find(|repeats| !repeats.is_empty())
+            .unwrap();
+        assert_eq!(ecb_repeats.len(), 1);
+        assert_eq!(ecb_repeats[0].positions, vec![1, 3, 5, 7]);
+        assert_eq!(
+            render_repeat_list(&find_block_repeats(data, 4)),
+            "A\t41414141\t0,2,5\nB\t42424242\t1,4\n"
+        );
+    }
+
+    #[test]
+    fn render_blocks_should_pass() {
+        let options = HexdumpOptions {
+            block_size: 4,
+            ..HexdumpOptions::default()
+        };
+        let dump = render_blocks(b"abcdXYZ\x00abcdab", &options);
+        assert_eq!(
+            dump,
+            "00000000  61 62 63 64 |abcd|  [A]\n\
+             00000004  58 59 5a 00 |XYZ.|\n\
+             00000008  61 62 63 64 |abcd|  [A]\n\
+             0000000c  61 62       |ab  |\n"
+        );
+        let colored = render_blocks(
+            b"abcdabcd",
+            &HexdumpOptions {
+                color: true,
+                ascii: false,
+                ..options
+            },
+        );
+        assert_eq!(
+            colored,
+            "00000000  \x1b[31m61 62 63 64\x1b[0m  [A]\n\
+             00000004  \x1b[31m61 62 63 64\x1b[0m  [A]\n"
+        );
+    }
+
+    #[test]
+    fn diff_blocks_should_pass() {
+        assert_eq!(
+            diff_blocks(&b"aaaabbbbcccc"[..], &b"aaaaxxxx"[..], 4),
+            vec![BlockDiff::Same, BlockDiff::Changed, BlockDiff::Removed]
+        );
+        assert_eq!(
+            diff_blocks(&b"aaaa"[..], &b"aaaabb"[..], 4),
+            vec![BlockDiff::Same, BlockDiff::Added]
+        );
+        let mut options = HexdumpOptions {
+            block_size: 2,
+            ascii: false,
+            ..HexdumpOptions::default()
+        };
+        assert_eq!(
+            render_block_diff(&b"aabb"[..], &b"aacc11"[..], &options),
+            "00000000  61 61  =  61 61\n\
+             00000002  62 62  !  63 63\n\
+             00000004         >  31 31\n"
+        );
+        options.ascii = true;
+        assert_eq!(
+            render_block_diff(&b"aab"[..], &b"a\n"[..], &options),
+            "00000000  61 61 |aa|  !  61 0a |a.|\n\
+             00000002  62    |b |  <        |  |\n"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+/// Loading of hex, base64 and raw input files
+use crate::base64_io::Base64Reader;
+use crate::codec::{Codec, CodecError, Hex};
+use crate::task_1::{Base64Config, Base64Error};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum InputError {
+    /// File can't be opened or read.
+    Io(io::Error),
+    /// Base64 content is malformed, offset is counted from the start of the file.
+    Base64(Base64Error),
+    /// Line `line` (counted from 1) is not a valid hex string.
+    Hex { line: usize, source: CodecError },
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Io(err) => write!(f, "failed to read input: {}", err),
+            InputError::Base64(err) => write!(f, "malformed base64 input: {}", err),
+            InputError::Hex { line, source } => {
+                write!(f, "malformed hex input on line {}: {}", line, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InputError::Io(err) => Some(err),
+            InputError::Base64(err) => Some(err),
+            InputError::Hex { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<io::Error> for InputError {
+    /// Unwraps decoding errors produced by `Base64Reader`.
+    fn from(err: io::Error) -> Self {
+        if err.get_ref().is_some_and(|inner| inner.is::<Base64Error>()) {
+            let inner = err.into_inner().expect("checked above");
+            InputError::Base64(*inner.downcast::<Base64Error>().expect("checked above"))
+        } else {
+            InputError::Io(err)
+        }
+    }
+}
+
+impl From<Base64Error> for InputError {
+    fn from(err: Base64Error) -> Self {
+        InputError::Base64(err)
+    }
+}
+
+/// Decodes a base64 file, line breaks are skipped.
+pub fn load_base64_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, InputError> {
+    load_base64_file_with(path, Base64Config::STANDARD)
+}
+
+pub fn load_base64_file_with<P: AsRef<Path>>(
+    path: P,
+    config: Base64Config,
+) -> Result<Vec<u8>, InputError> {
+    let mut result = Vec::new();
+    Base64Reader::with_config(File::open(path)?, config).read_to_end(&mut result)?;
+    Ok(result)
+}
+
+/// Decodes every line of a file as a separate hex record, empty lines are skipped.
+pub fn load_hex_lines<P: AsRef<Path>>(path: P) -> Result<Vec<Vec<u8>>, InputError> {
+    let mut result = Vec::new();
+    for (idx, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = Hex::default()
+            .decode(&line)
+            .map_err(|source| InputError::Hex {
+                line: idx + 1,
+                source,
+            })?;
+        result.push(record);
+    }
+    Ok(result)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    Hex,
+    Base64,
+    Raw,
+}
+
+/// Guesses format of the content: hex if it consists of hex digit pairs, base64 if it
+/// decodes as whitespace-tolerant base64, raw otherwise. Empty input is considered raw.
+pub fn detect_format(content: &[u8]) -> InputFormat {
+    let significant = || content.iter().filter(|b| !b.is_ascii_whitespace());
+    let count = significant().count();
+    if count == 0 {
+        return InputFormat::Raw;
+    }
+    if count % 2 == 0 && significant().all(|b| b.is_ascii_hexdigit()) {
+        return InputFormat::Hex;
+    }
+    let is_base64 = std::str::from_utf8(content)
+        .ok()
+        .is_some_and(|text| lenient_base64().decode(text).is_ok());
+    if is_base64 {
+        InputFormat::Base64
+    } else {
+        InputFormat::Raw
+    }
+}
+
+/// Loads a file of any supported format, see [`detect_format`].
+pub fn load_auto<P: AsRef<Path>>(path: P) -> Result<(InputFormat, Vec<u8>), InputError> {
+    let mut content = Vec::new();
+    File::open(path)?.read_to_end(&mut content)?;
+    let format = detect_format(&content);
+    let data = match format {
+        InputFormat::Raw => content,
+        InputFormat::Hex => {
+            let digits = content
+                .into_iter()
+                .filter(|b| !b.is_ascii_whitespace())
+                .map(|b| b as char)
+                .collect::<String>();
+            Hex::default()
+                .decode(&digits)
+                .expect("validated by detect_format")
+        }
+        InputFormat::Base64 => {
+            let text = String::from_utf8(content).expect("base64 input is ASCII");
+            lenient_base64().decode(&text)?
+        }
+    };
+    Ok((format, data))
+}
+
+fn lenient_base64() -> Base64Config {
+    Base64Config {
+        ignore_whitespace: true,
+        ..Base64Config::STANDARD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_files_should_pass() {
+        let cipher = load_base64_file("res/task6.txt").unwrap();
+        assert_eq!(cipher.len(), 2876);
+        let records = load_hex_lines("res/task8.txt").unwrap();
+        assert_eq!(records.len(), 204);
+        assert!(records.iter().all(|record| record.len() == 160));
+
+        assert!(matches!(
+            load_base64_file("res/missing.txt"),
+            Err(InputError::Io(_))
+        ));
+        let path =
+            std::env::temp_dir().join(format!("set_1_input_malformed_{}.txt", std::process::id()));
+        std::fs::write(&path, "Zm9v\nYm!y\n").unwrap();
+        let malformed = load_base64_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            malformed,
+            Err(InputError::Base64(Base64Error::ParsingFailed {
+                offset: 7,
+                ch: '!'
+            }))
+        ));
+        assert!(matches!(
+            load_hex_lines("res/task6.txt"),
+            Err(InputError::Hex { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn detect_format_should_pass() {
+        assert_eq!(detect_format(b"de ad\nBEEF\n"), InputFormat::Hex);
+        assert_eq!(detect_format(b"Zm9v\nYmFy\n"), InputFormat::Base64);
+        assert_eq!(detect_format(b"Zm9vYg=="), InputFormat::Base64);
+        assert_eq!(detect_format(b"abc"), InputFormat::Raw);
+        assert_eq!(detect_format(b"hello world"), InputFormat::Raw);
+        assert_eq!(detect_format(&[0xff, 0x00, 0x10]), InputFormat::Raw);
+        assert_eq!(detect_format(b""), InputFormat::Raw);
+
+        let (format, data) = load_auto("res/task7.txt").unwrap();
+        assert_eq!(format, InputFormat::Base64);
+        assert_eq!(data, load_base64_file("res/task7.txt").unwrap());
+        let (format, data) = load_auto("res/task8.txt").unwrap();
+        assert_eq!(format, InputFormat::Hex);
+        assert_eq!(data, load_hex_lines("res/task8.txt").unwrap().concat());
+    }
+}
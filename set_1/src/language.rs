@@ -0,0 +1,355 @@
+/// Language models trained from corpora, stored in a simple text format
+use crate::codec::{Codec, Hex};
+use crate::scoring::{ChiSquared, HistogramScorer, NgramModel, PlaintextScorer, ENGLISH_BIGRAMS};
+use crate::task_3::ENGLISH_FREQUENCIES;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Languages available through [`LanguageModel::bundled`].
+pub const BUNDLED_LANGUAGES: [&str; 5] = ["english", "german", "french", "spanish", "italian"];
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(io::Error),
+    /// Line `line` (counted from 1) is not a `<hex n-gram> <weight>` pair.
+    InvalidLine {
+        line: usize,
+    },
+    /// N-gram on line `line` has a different length than the preceding ones.
+    NgramLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelError::Io(err) => write!(f, "failed to access model: {}", err),
+            ModelError::InvalidLine { line } => write!(f, "malformed model line {}", line),
+            ModelError::NgramLength {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "n-gram on line {} has length {}, expected {}",
+                line, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModelError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ModelError {
+    fn from(err: io::Error) -> Self {
+        ModelError::Io(err)
+    }
+}
+
+/// Unigram byte weights with optional letter n-gram weights.
+///
+/// Bytes are case-folded, non-ASCII text is modelled byte-wise so multibyte UTF-8
+/// sequences contribute to both tables. Weights are kept as given, e.g. trained models
+/// store raw counts. Text format has one `<hex n-gram> <weight>` pair per line,
+/// lines starting with `#` are comments.
+#[derive(Clone, Debug)]
+pub struct LanguageModel {
+    unigrams: BTreeMap<u8, f32>,
+    ngrams: BTreeMap<Vec<u8>, f32>,
+    unigram_total: f32,
+    chi_squared: ChiSquared,
+    ngram_model: Option<NgramModel>,
+}
+
+/// Scorers are derived from the tables, so only the tables are compared.
+impl PartialEq for LanguageModel {
+    fn eq(&self, other: &Self) -> bool {
+        self.unigrams == other.unigrams && self.ngrams == other.ngrams
+    }
+}
+
+impl LanguageModel {
+    /// Weights don't have to be normalized, all n-grams must be of the same length.
+    pub fn new<U, N>(unigrams: U, ngrams: N) -> Self
+    where
+        U: IntoIterator<Item = (u8, f32)>,
+        N: IntoIterator<Item = (Vec<u8>, f32)>,
+    {
+        let unigrams = merge(
+            unigrams
+                .into_iter()
+                .map(|(b, f)| (b.to_ascii_lowercase(), f)),
+        );
+        let ngrams = merge(ngrams.into_iter().map(|(g, f)| (g.to_ascii_lowercase(), f)));
+        let n = ngrams.keys().next().map(Vec::len);
+        assert!(
+            ngrams.keys().all(|gram| Some(gram.len()) == n),
+            "N-grams must be of the same length"
+        );
+        Self {
+            unigram_total: unigrams.values().sum(),
+            chi_squared: ChiSquared::new(unigrams.iter().map(|(&b, &f)| (b, f))),
+            ngram_model: n.map(|n| NgramModel::new(n, ngrams.iter().map(|(g, &f)| (g, f)))),
+            unigrams,
+            ngrams,
+        }
+    }
+
+    /// Counts bytes and letter n-grams of length `n`, `n == 1` produces unigrams only.
+    pub fn train<T: AsRef<[u8]>>(corpus: T, n: usize) -> Self {
+        assert!(n > 0, "N-gram length must be positive");
+        let corpus = corpus.as_ref().to_ascii_lowercase();
+        let mut unigrams = BTreeMap::<u8, f32>::new();
+        for &b in corpus.iter() {
+            *unigrams.entry(b).or_default() += 1.0;
+        }
+        let mut ngrams = BTreeMap::<Vec<u8>, f32>::new();
+        if n > 1 {
+            for window in corpus.windows(n) {
+                if window.iter().all(|&b| b.is_ascii_alphabetic() || b >= 0x80) {
+                    *ngrams.entry(window.to_vec()).or_default() += 1.0;
+                }
+            }
+        }
+        Self::new(unigrams, ngrams)
+    }
+
+    pub fn train_file<P: AsRef<Path>>(path: P, n: usize) -> Result<Self, ModelError> {
+        let mut corpus = Vec::new();
+        File::open(path)?.read_to_end(&mut corpus)?;
+        Ok(Self::train(corpus, n))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ModelError> {
+        let mut unigrams = Vec::new();
+        let mut ngrams = Vec::<(Vec<u8>, f32)>::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || ModelError::InvalidLine { line: idx + 1 };
+            let (gram, weight) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let gram = Hex::default().decode(gram).map_err(|_| invalid())?;
+            let weight = weight
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|f| f.is_finite() && *f >= 0.0)
+                .ok_or_else(invalid)?;
+            match gram.len() {
+                0 => return Err(invalid()),
+                1 => unigrams.push((gram[0], weight)),
+                found => match ngrams.first() {
+                    Some((first, _)) if first.len() != found => {
+                        return Err(ModelError::NgramLength {
+                            line: idx + 1,
+                            expected: first.len(),
+                            found,
+                        })
+                    }
+                    _ => ngrams.push((gram, weight)),
+                },
+            }
+        }
+        Ok(Self::new(unigrams, ngrams))
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Self, ModelError> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        Self::parse(&text)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let unigrams = self.unigrams.iter().map(|(&b, &f)| (vec![b], f));
+        let ngrams = self.ngrams.iter().map(|(g, &f)| (g.clone(), f));
+        for (gram, weight) in unigrams.chain(ngrams) {
+            writeln!(out, "{} {}", Hex::default().encode(&gram), weight)
+                .expect("writing to string");
+        }
+        out
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ModelError> {
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /// Unigrams from `ENGLISH_FREQUENCIES` with the most frequent bigrams.
+    pub fn english() -> Self {
+        Self::new(
+            ENGLISH_FREQUENCIES
+                .iter()
+                .map(|(&ch, &freq)| (ch as u8, freq)),
+            ENGLISH_BIGRAMS
+                .iter()
+                .map(|(gram, freq)| (gram.as_bytes().to_vec(), *freq)),
+        )
+    }
+
+    /// Model of one of [`BUNDLED_LANGUAGES`], non-English ones have unigrams only.
+    pub fn bundled(language: &str) -> Option<Self> {
+        let text = match language {
+            "english" => return Some(Self::english()),
+            "german" => include_str!("../res/models/german.txt"),
+            "french" => include_str!("../res/models/french.txt"),
+            "spanish" => include_str!("../res/models/spanish.txt"),
+            "italian" => include_str!("../res/models/italian.txt"),
+            _ => return None,
+        };
+        Some(Self::parse(text).expect("bundled model is valid"))
+    }
+
+    /// Normalized frequency of the case-folded byte, zero if it's absent.
+    pub fn frequency(&self, b: u8) -> f32 {
+        self.unigrams
+            .get(&b.to_ascii_lowercase())
+            .map_or(0.0, |weight| weight / self.unigram_total)
+    }
+
+    pub fn chi_squared(&self) -> &ChiSquared {
+        &self.chi_squared
+    }
+
+    pub fn ngram_model(&self) -> Option<&NgramModel> {
+        self.ngram_model.as_ref()
+    }
+}
+
+/// Scores by chi-squared distance of unigrams, see [`LanguageModel::ngram_model`]
+/// for n-gram scoring.
+impl PlaintextScorer for LanguageModel {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        self.chi_squared.score(plaintext)
+    }
+}
+
+impl HistogramScorer for LanguageModel {
+    fn score_histogram(&self, histogram: &[u32; 256], key: u8) -> f32 {
+        self.chi_squared.score_histogram(histogram, key)
+    }
+}
+
+/// Sums weights of equal keys, drops zero weights.
+fn merge<K: Ord, I: IntoIterator<Item = (K, f32)>>(weights: I) -> BTreeMap<K, f32> {
+    let mut result = BTreeMap::<K, f32>::new();
+    for (key, weight) in weights {
+        *result.entry(key).or_default() += weight;
+    }
+    result.retain(|_, weight| *weight > 0.0);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::load_base64_file;
+    use crate::scoring::Printable;
+    use crate::task_3::brute_single_byte_xor_with;
+    use crate::task_5::repeating_key_xor;
+
+    fn xor(data: &[u8], key: u8) -> Vec<u8> {
+        data.iter().map(|b| b ^ key).collect()
+    }
+
+    #[test]
+    fn train_and_save_model_should_pass() {
+        let cipher = load_base64_file("res/task6.txt").unwrap();
+        let corpus = repeating_key_xor(&cipher[..], b"Terminator X: Bring the noise");
+        let id = std::process::id();
+        let corpus_path = std::env::temp_dir().join(format!("set_1_language_corpus_{}.txt", id));
+        std::fs::write(&corpus_path, &corpus).unwrap();
+
+        let model = LanguageModel::train_file(&corpus_path, 2).unwrap();
+        std::fs::remove_file(&corpus_path).unwrap();
+        assert_eq!(model, LanguageModel::train(&corpus, 2));
+        assert_eq!(model.ngram_model().map(NgramModel::n), Some(2));
+        assert!(model.frequency(b' ') > model.frequency(b'e'));
+        assert_eq!(model.frequency(b'I'), model.frequency(b'i'));
+
+        let model_path = std::env::temp_dir().join(format!("set_1_language_model_{}.txt", id));
+        model.save_file(&model_path).unwrap();
+        let loaded = LanguageModel::load_file(&model_path).unwrap();
+        std::fs::remove_file(&model_path).unwrap();
+        assert_eq!(loaded, model);
+
+        let cipher = xor(b"Cooking MC's like a pound of bacon", 88);
+        let (key, ..) = brute_single_byte_xor_with(&cipher, &model).unwrap();
+        assert_eq!(key, 88);
+    }
+
+    #[test]
+    fn parse_model_should_pass() {
+        let model = LanguageModel::parse("# comment\n\n61 3\n62 1\n6162 1.5\n").unwrap();
+        assert_eq!(model.frequency(b'a'), 0.75);
+        assert_eq!(model.to_text(), "61 3\n62 1\n6162 1.5\n");
+        assert!(matches!(
+            LanguageModel::parse("61 0.5\nzz 0.5\n"),
+            Err(ModelError::InvalidLine { line: 2 })
+        ));
+        assert!(matches!(
+            LanguageModel::parse("61 -1\n"),
+            Err(ModelError::InvalidLine { line: 1 })
+        ));
+        assert!(matches!(
+            LanguageModel::parse("6162 1\n616263 1\n"),
+            Err(ModelError::NgramLength {
+                line: 2,
+                expected: 2,
+                found: 3
+            })
+        ));
+        assert!(matches!(
+            LanguageModel::load_file("res/models/missing.txt"),
+            Err(ModelError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn bundled_models_should_pass() {
+        let samples: [(&str, &str); 4] = [
+            (
+                "german",
+                "Die Würde des Menschen ist unantastbar, sie zu schützen ist Pflicht",
+            ),
+            (
+                "french",
+                "Les hommes naissent et demeurent libres et égaux en droits",
+            ),
+            (
+                "spanish",
+                "En un lugar de la Mancha, de cuyo nombre no quiero acordarme",
+            ),
+            (
+                "italian",
+                "Nel mezzo del cammin di nostra vita mi ritrovai per una selva oscura",
+            ),
+        ];
+        for (language, sample) in samples {
+            let model = LanguageModel::bundled(language).unwrap();
+            let cipher = xor(sample.as_bytes(), 0x5a);
+            let scorer = (Printable::Utf8, &model);
+            let (key, guess, _) = brute_single_byte_xor_with(&cipher, &scorer).unwrap();
+            assert_eq!((key, guess.as_slice()), (0x5a, sample.as_bytes()));
+        }
+        assert!(BUNDLED_LANGUAGES
+            .iter()
+            .all(|language| LanguageModel::bundled(language).is_some()));
+        assert!(LanguageModel::bundled("klingon").is_none());
+    }
+}
@@ -1,4 +1,3 @@
-#![feature(slice_as_chunks)]
 pub mod task_1;
 pub mod task_2;
 pub mod task_3;
@@ -8,6 +7,23 @@ pub mod task_6;
 pub mod task_7;
 pub mod task_8;
 
+pub mod aes;
+pub mod base64_io;
+pub mod block_cipher;
+pub mod cipher_io;
+pub mod classical;
+pub mod codec;
+pub mod crypter;
+pub mod ctr;
+pub mod distance;
+pub mod gcm;
+pub mod gcm_attack;
+pub mod hexdump;
+pub mod input;
+pub mod language;
+pub mod padding;
+pub mod scoring;
+
 pub mod prelude {
     pub use crate::task_1::*;
     pub use crate::task_2::*;
@@ -17,4 +33,21 @@ pub mod prelude {
     pub use crate::task_6::*;
     pub use crate::task_7::*;
     pub use crate::task_8::*;
+
+    pub use crate::aes::*;
+    pub use crate::base64_io::*;
+    pub use crate::block_cipher::*;
+    pub use crate::cipher_io::*;
+    pub use crate::classical::*;
+    pub use crate::codec::*;
+    pub use crate::crypter::*;
+    pub use crate::ctr::*;
+    pub use crate::distance::*;
+    pub use crate::gcm::*;
+    pub use crate::gcm_attack::*;
+    pub use crate::hexdump::*;
+    pub use crate::input::*;
+    pub use crate::language::*;
+    pub use crate::padding::*;
+    pub use crate::scoring::*;
 }
@@ -0,0 +1,359 @@
+/// Block cipher padding schemes with validated removal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddingError {
+    /// Input is empty or not aligned to block size.
+    InvalidLength { len: usize, block_size: usize },
+    /// Pad length is out of range or pad bytes are inconsistent.
+    BadPadding,
+    /// Block size is zero or too large for the pad length byte.
+    InvalidBlockSize { block_size: usize },
+}
+
+impl std::fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaddingError::InvalidLength { len, block_size } => write!(
+                f,
+                "padded input of {} bytes is not aligned to block size {}",
+                len, block_size
+            ),
+            PaddingError::BadPadding => write!(f, "bad padding"),
+            PaddingError::InvalidBlockSize { block_size } => {
+                write!(f, "block size {} is not supported", block_size)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PaddingError {}
+
+impl From<PaddingError> for std::io::Error {
+    fn from(err: PaddingError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// How pad bytes are checked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaddingCheck {
+    /// Stops at the first bad byte.
+    #[default]
+    EarlyExit,
+    /// Always inspects the whole last block, timing doesn't depend on pad bytes.
+    ConstantTime,
+}
+
+/// Schemes which store pad length in a byte support block sizes up to 255.
+fn check_byte_block_size(block_size: usize) -> Result<(), PaddingError> {
+    if !(1..=255).contains(&block_size) {
+        return Err(PaddingError::InvalidBlockSize { block_size });
+    }
+    Ok(())
+}
+
+fn check_block_size(block_size: usize) -> Result<(), PaddingError> {
+    if block_size == 0 {
+        return Err(PaddingError::InvalidBlockSize { block_size });
+    }
+    Ok(())
+}
+
+/// Length of the pad which fills the last block, a whole block for aligned data.
+fn pad_len(data: &[u8], block_size: usize) -> usize {
+    block_size - data.len() % block_size
+}
+
+fn check_length(data: &[u8], block_size: usize) -> Result<(), PaddingError> {
+    check_block_size(block_size)?;
+    if data.is_empty() || !data.len().is_multiple_of(block_size) {
+        return Err(PaddingError::InvalidLength {
+            len: data.len(),
+            block_size,
+        });
+    }
+    Ok(())
+}
+
+pub fn unpad_pkcs7(data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    unpad_pkcs7_with(data, block_size, PaddingCheck::EarlyExit)
+}
+
+/// Every pad byte must be equal to the pad length in `1..=block_size`.
+pub fn unpad_pkcs7_with(
+    data: &[u8],
+    block_size: usize,
+    check: PaddingCheck,
+) -> Result<Vec<u8>, PaddingError> {
+    check_byte_block_size(block_size)?;
+    check_length(data, block_size)?;
+    let last_block = &data[data.len() - block_size..];
+    let value = *last_block.last().expect("input is not empty");
+    let valid = match check {
+        PaddingCheck::EarlyExit => {
+            (1..=block_size).contains(&(value as usize))
+                && last_block[block_size - value as usize..]
+                    .iter()
+                    .all(|&b| b == value)
+        }
+        PaddingCheck::ConstantTime => pkcs7_valid_constant_time(last_block, value),
+    };
+    if !valid {
+        return Err(PaddingError::BadPadding);
+    }
+    Ok(data[..data.len() - value as usize].to_vec())
+}
+
+/// Branch-free check, bytes of the block are visited back to front.
+fn pkcs7_valid_constant_time(last_block: &[u8], value: u8) -> bool {
+    let len = last_block.len() as u16;
+    // 0xff when `value` is zero or exceeds block size
+    let mut diff = (((value as u16).wrapping_sub(1) | len.wrapping_sub(value as u16)) >> 8) as u8;
+    for (idx, &b) in last_block.iter().rev().enumerate() {
+        // 0xff for bytes inside of the pad
+        let in_pad = ((idx as u16).wrapping_sub(value as u16) >> 8) as u8;
+        diff |= (b ^ value) & in_pad;
+    }
+    std::hint::black_box(diff) == 0
+}
+
+pub trait Padding {
+    /// Output is aligned to block size. All schemes but zero padding add at
+    /// least one byte, so aligned input gets a whole extra block.
+    fn pad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError>;
+    fn unpad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError>;
+}
+
+/// PKCS#7: every pad byte holds the pad length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pkcs7 {
+    pub check: PaddingCheck,
+}
+
+impl Padding for Pkcs7 {
+    fn pad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        check_byte_block_size(block_size)?;
+        let pad = pad_len(data, block_size);
+        let mut output = data.to_vec();
+        output.resize(data.len() + pad, pad as u8);
+        Ok(output)
+    }
+
+    fn unpad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        unpad_pkcs7_with(data, block_size, self.check)
+    }
+}
+
+/// Removes the pad whose length is stored in the last byte, `filler` checks the rest of it.
+fn unpad_length_byte<F>(data: &[u8], block_size: usize, filler: F) -> Result<Vec<u8>, PaddingError>
+where
+    F: Fn(u8) -> bool,
+{
+    check_byte_block_size(block_size)?;
+    check_length(data, block_size)?;
+    let value = *data.last().expect("input is not empty") as usize;
+    if !(1..=block_size).contains(&value)
+        || !data[data.len() - value..data.len() - 1]
+            .iter()
+            .all(|&b| filler(b))
+    {
+        return Err(PaddingError::BadPadding);
+    }
+    Ok(data[..data.len() - value].to_vec())
+}
+
+/// ANSI X.923: zeros followed by the pad length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AnsiX923;
+
+impl Padding for AnsiX923 {
+    fn pad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        check_byte_block_size(block_size)?;
+        let pad = pad_len(data, block_size);
+        let mut output = data.to_vec();
+        output.resize(data.len() + pad - 1, 0);
+        output.push(pad as u8);
+        Ok(output)
+    }
+
+    fn unpad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        unpad_length_byte(data, block_size, |b| b == 0)
+    }
+}
+
+/// ISO 10126: random bytes followed by the pad length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Iso10126;
+
+impl Padding for Iso10126 {
+    fn pad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        check_byte_block_size(block_size)?;
+        let pad = pad_len(data, block_size);
+        let mut output = data.to_vec();
+        output.extend((1..pad).map(|_| rand::random::<u8>()));
+        output.push(pad as u8);
+        Ok(output)
+    }
+
+    fn unpad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        unpad_length_byte(data, block_size, |_| true)
+    }
+}
+
+/// ISO/IEC 7816-4 bit padding: `0x80` followed by zeros, any block size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Iso7816;
+
+impl Padding for Iso7816 {
+    fn pad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        check_block_size(block_size)?;
+        let pad = pad_len(data, block_size);
+        let mut output = data.to_vec();
+        output.push(0x80);
+        output.resize(data.len() + pad, 0);
+        Ok(output)
+    }
+
+    fn unpad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        check_length(data, block_size)?;
+        let last_block = &data[data.len() - block_size..];
+        match last_block.iter().rposition(|&b| b != 0) {
+            Some(idx) if last_block[idx] == 0x80 => {
+                Ok(data[..data.len() - block_size + idx].to_vec())
+            }
+            _ => Err(PaddingError::BadPadding),
+        }
+    }
+}
+
+/// Zeros up to block size, aligned data is left as is. Trailing zeros of
+/// the data can't be told apart from the pad and are removed as well.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZeroPadding;
+
+impl Padding for ZeroPadding {
+    /// Unlike other schemes, aligned data doesn't get an extra block.
+    fn pad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        check_block_size(block_size)?;
+        let mut output = data.to_vec();
+        output.resize(data.len().next_multiple_of(block_size), 0);
+        Ok(output)
+    }
+
+    fn unpad(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        check_block_size(block_size)?;
+        if !data.len().is_multiple_of(block_size) {
+            return Err(PaddingError::InvalidLength {
+                len: data.len(),
+                block_size,
+            });
+        }
+        let last_block_start = data.len().saturating_sub(block_size);
+        let end = data[last_block_start..]
+            .iter()
+            .rposition(|&b| b != 0)
+            .map_or(last_block_start, |idx| last_block_start + idx + 1);
+        Ok(data[..end].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpad_pkcs7_should_pass() {
+        for check in [PaddingCheck::EarlyExit, PaddingCheck::ConstantTime] {
+            let unpad = |data: &[u8]| unpad_pkcs7_with(data, 4, check);
+            assert_eq!(
+                unpad(b"ICE ICE BABY\x04\x04\x04\x04"),
+                Ok(b"ICE ICE BABY".to_vec())
+            );
+            assert_eq!(
+                unpad(b"ICE ICE BABY1\x03\x03\x03"),
+                Ok(b"ICE ICE BABY1".to_vec())
+            );
+            assert_eq!(
+                unpad(b"ICE ICE BABY123\x01"),
+                Ok(b"ICE ICE BABY123".to_vec())
+            );
+            assert_eq!(
+                unpad(b"ICE ICE BABY\x05\x05\x05\x05"),
+                Err(PaddingError::BadPadding)
+            );
+            assert_eq!(
+                unpad(b"ICE ICE BABY\x01\x02\x03\x04"),
+                Err(PaddingError::BadPadding)
+            );
+            assert_eq!(
+                unpad(b"ICE ICE BABY12\x01\x02"),
+                Err(PaddingError::BadPadding)
+            );
+            assert_eq!(unpad(b"ICE ICE BABY123\x00"), Err(PaddingError::BadPadding));
+            assert_eq!(
+                unpad(b"ICE ICE BABY\x04\x04\x04"),
+                Err(PaddingError::InvalidLength {
+                    len: 15,
+                    block_size: 4
+                })
+            );
+            assert!(unpad(b"").is_err());
+        }
+        let padded = [16u8; 16];
+        assert_eq!(unpad_pkcs7(&padded, 16), Ok(vec![]));
+        assert_eq!(
+            unpad_pkcs7_with(&padded, 16, PaddingCheck::ConstantTime),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn padding_schemes_should_pass() {
+        let schemes: [&dyn Padding; 5] = [
+            &Pkcs7::default(),
+            &AnsiX923,
+            &Iso10126,
+            &Iso7816,
+            &ZeroPadding,
+        ];
+        for scheme in schemes {
+            for len in 0..=9 {
+                let data = vec![0xaa; len];
+                let padded = scheme.pad(&data, 4).unwrap();
+                assert_eq!(padded.len() % 4, 0);
+                assert_eq!(scheme.unpad(&padded, 4), Ok(data));
+            }
+            assert!(scheme.unpad(b"abcde", 4).is_err());
+        }
+        assert_eq!(AnsiX923.pad(b"abcde", 4).unwrap(), b"abcde\0\0\x03");
+        assert_eq!(
+            AnsiX923.unpad(b"abcde\0\x01\x03", 4),
+            Err(PaddingError::BadPadding)
+        );
+        assert_eq!(Iso10126.pad(b"abcde", 4).unwrap()[7], 3);
+        assert_eq!(
+            Iso10126.unpad(b"abcde\x17\x01\x03", 4),
+            Ok(b"abcde".to_vec())
+        );
+        assert_eq!(Iso7816.pad(b"abcde", 4).unwrap(), b"abcde\x80\0\0");
+        assert_eq!(Iso7816.pad(b"abcd", 300).unwrap().len(), 300);
+        assert_eq!(
+            Iso7816.unpad(b"abcde\x80\0\x01", 4),
+            Err(PaddingError::BadPadding)
+        );
+        assert_eq!(
+            Iso7816.unpad(b"abcd\0\0\0\0", 4),
+            Err(PaddingError::BadPadding)
+        );
+        assert_eq!(ZeroPadding.pad(b"abcd", 4).unwrap(), b"abcd");
+        for block_size in [0, 256] {
+            let error = Err(PaddingError::InvalidBlockSize { block_size });
+            assert_eq!(Pkcs7::default().pad(b"abcd", block_size), error);
+            assert_eq!(AnsiX923.unpad(b"abcd", block_size), error);
+        }
+        assert_eq!(
+            ZeroPadding.pad(b"abcd", 0),
+            Err(PaddingError::InvalidBlockSize { block_size: 0 })
+        );
+        assert_eq!(ZeroPadding.unpad(b"ab\0\0", 4), Ok(b"ab".to_vec()));
+    }
+}
@@ -0,0 +1,463 @@
+/// Scoring of candidate plaintexts
+use crate::task_3::{rate_english_frequency, ENGLISH_FREQUENCIES};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+/// Expected frequency of bytes which are neither printable nor whitespace.
+const INVALID_FREQUENCY: f32 = 1e-4;
+/// Lower bound for frequency of printable bytes which are not present in the table.
+const MIN_OTHER_FREQUENCY: f32 = 1e-3;
+
+/// Rates how likely the data is a plaintext, higher is better.
+///
+/// Scores are only comparable between candidates of the same length,
+/// `f32::NEG_INFINITY` means that the candidate is rejected.
+pub trait PlaintextScorer {
+    fn score(&self, plaintext: &[u8]) -> f32;
+}
+
+/// Scorer which only depends on byte counts, so a single-byte XOR key can be
+/// rated from a histogram of the ciphertext without decrypting it.
+///
+/// Scores may differ from [`PlaintextScorer::score`] by rounding errors.
+pub trait HistogramScorer: PlaintextScorer {
+    /// Rates the plaintext in which byte `b` occurs `histogram[b ^ key]` times.
+    fn score_histogram(&self, histogram: &[u32; 256], key: u8) -> f32;
+}
+
+/// Both scorers must accept the candidate, scores are summed.
+impl<A: PlaintextScorer, B: PlaintextScorer> PlaintextScorer for (A, B) {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        self.0.score(plaintext) + self.1.score(plaintext)
+    }
+}
+
+impl<A: HistogramScorer, B: HistogramScorer> HistogramScorer for (A, B) {
+    fn score_histogram(&self, histogram: &[u32; 256], key: u8) -> f32 {
+        self.0.score_histogram(histogram, key) + self.1.score_histogram(histogram, key)
+    }
+}
+
+impl<S: PlaintextScorer + ?Sized> PlaintextScorer for &S {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        (**self).score(plaintext)
+    }
+}
+
+impl<S: HistogramScorer + ?Sized> HistogramScorer for &S {
+    fn score_histogram(&self, histogram: &[u32; 256], key: u8) -> f32 {
+        (**self).score_histogram(histogram, key)
+    }
+}
+
+pub fn byte_histogram<T: AsRef<[u8]>>(data: T) -> [u32; 256] {
+    let mut histogram = [0u32; 256];
+    for &b in data.as_ref() {
+        histogram[b as usize] += 1;
+    }
+    histogram
+}
+
+/// Counts of plaintext bytes, see [`HistogramScorer::score_histogram`].
+fn plaintext_counts(histogram: &[u32; 256], key: u8) -> impl Iterator<Item = (u8, u32)> + '_ {
+    (0u8..=255)
+        .map(move |b| (b, histogram[(b ^ key) as usize]))
+        .filter(|(_, count)| *count > 0)
+}
+
+lazy_static! {
+    static ref ENGLISH_WEIGHTS: [f32; 256] = {
+        let mut weights = [0.0; 256];
+        for (&ch, &freq) in ENGLISH_FREQUENCIES.iter() {
+            weights[ch as usize] = freq;
+        }
+        weights
+    };
+}
+
+fn is_text_byte(b: u8) -> bool {
+    matches!(b, 0x20..=0x7e | b'\t' | b'\n' | b'\r')
+}
+
+/// ASCII letter or a part of multibyte UTF-8 sequence.
+fn is_letter_byte(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b >= 0x80
+}
+
+fn is_word_separator(b: u8) -> bool {
+    b.is_ascii_whitespace() || b"(),-.:;!?'\"".contains(&b)
+}
+
+/// Sum of unigram weights, see [`rate_english_frequency`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrequencySum;
+
+impl PlaintextScorer for FrequencySum {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        rate_english_frequency(plaintext)
+    }
+}
+
+impl HistogramScorer for FrequencySum {
+    fn score_histogram(&self, histogram: &[u32; 256], key: u8) -> f32 {
+        plaintext_counts(histogram, key)
+            .map(|(b, count)| count as f32 * ENGLISH_WEIGHTS[b as usize])
+            .sum()
+    }
+}
+
+/// Filter which rejects candidates containing control characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Printable {
+    /// Only printable ASCII and whitespace.
+    Ascii,
+    /// Any valid UTF-8 without control characters other than whitespace.
+    Utf8,
+}
+
+impl PlaintextScorer for Printable {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        let valid = match self {
+            Printable::Ascii => plaintext.iter().all(|&b| is_text_byte(b)),
+            Printable::Utf8 => std::str::from_utf8(plaintext).is_ok_and(|text| {
+                text.chars()
+                    .all(|ch| !ch.is_control() || matches!(ch, '\t' | '\n' | '\r'))
+            }),
+        };
+        if valid {
+            0.0
+        } else {
+            f32::NEG_INFINITY
+        }
+    }
+}
+
+/// Only ASCII filter can be decided by byte counts, UTF-8 one is always accepted.
+impl HistogramScorer for Printable {
+    fn score_histogram(&self, histogram: &[u32; 256], key: u8) -> f32 {
+        let valid = match self {
+            Printable::Ascii => plaintext_counts(histogram, key).all(|(b, _)| is_text_byte(b)),
+            Printable::Utf8 => true,
+        };
+        if valid {
+            0.0
+        } else {
+            f32::NEG_INFINITY
+        }
+    }
+}
+
+/// Negated chi-squared distance between case-folded byte counts and expected frequencies.
+///
+/// Printable ASCII bytes absent from the table share a single bin, other bytes fall
+/// into a bin with a tiny expected frequency.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChiSquared {
+    expected: HashMap<u8, f32>,
+    other: f32,
+}
+
+impl ChiSquared {
+    /// Frequencies are normalized, keys are lowercased.
+    pub fn new<I: IntoIterator<Item = (u8, f32)>>(frequencies: I) -> Self {
+        let mut expected = HashMap::new();
+        for (b, freq) in frequencies {
+            *expected.entry(b.to_ascii_lowercase()).or_default() += freq;
+        }
+        let total = expected.values().sum::<f32>();
+        let other = (1.0 - total).max(MIN_OTHER_FREQUENCY);
+        let norm = total + other + INVALID_FREQUENCY;
+        Self {
+            expected: expected.into_iter().map(|(b, f)| (b, f / norm)).collect(),
+            other: other / norm,
+        }
+    }
+
+    pub fn english() -> Self {
+        Self::new(
+            ENGLISH_FREQUENCIES
+                .iter()
+                .map(|(&ch, &freq)| (ch as u8, freq)),
+        )
+    }
+}
+
+impl PlaintextScorer for ChiSquared {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        self.score_histogram(&byte_histogram(plaintext), 0)
+    }
+}
+
+impl HistogramScorer for ChiSquared {
+    fn score_histogram(&self, histogram: &[u32; 256], key: u8) -> f32 {
+        let mut counts = [0u32; 256];
+        let (mut other, mut invalid, mut len) = (0, 0, 0);
+        for (b, count) in plaintext_counts(histogram, key) {
+            let b = b.to_ascii_lowercase();
+            if self.expected.contains_key(&b) {
+                counts[b as usize] += count;
+            } else if is_text_byte(b) {
+                other += count;
+            } else {
+                invalid += count;
+            }
+            len += count;
+        }
+        let len = len as f32;
+        let term = |observed: u32, frequency: f32| {
+            let expected = frequency * len;
+            (observed as f32 - expected).powi(2) / expected
+        };
+        let chi = self
+            .expected
+            .iter()
+            .map(|(&b, &freq)| term(counts[b as usize], freq))
+            .sum::<f32>()
+            + term(other, self.other)
+            + term(invalid, INVALID_FREQUENCY);
+        -chi
+    }
+}
+
+/// Mean log10-likelihood of overlapping case-folded n-grams.
+///
+/// Table contains letter-only n-grams, bytes of multibyte UTF-8 sequences are considered
+/// letters. Windows crossing word boundaries are scored as an average n-gram and windows
+/// with digits, symbols or control bytes as an unseen one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NgramModel {
+    n: usize,
+    log_probs: HashMap<Vec<u8>, f32>,
+    /// Log-probability of unseen n-gram, ten times lower than of the rarest known one.
+    floor: f32,
+    /// Expected log-probability of a n-gram, used for word boundaries.
+    boundary: f32,
+}
+
+impl NgramModel {
+    /// Counts don't have to be normalized, n-grams of a wrong length or with
+    /// non-letters are ignored.
+    pub fn new<G, I>(n: usize, counts: I) -> Self
+    where
+        G: AsRef<[u8]>,
+        I: IntoIterator<Item = (G, f32)>,
+    {
+        assert!(n > 0, "N-gram length must be positive");
+        let mut grams = HashMap::<Vec<u8>, f32>::new();
+        for (gram, count) in counts {
+            let gram = gram.as_ref();
+            if gram.len() == n && gram.iter().all(|&b| is_letter_byte(b)) {
+                *grams.entry(gram.to_ascii_lowercase()).or_default() += count;
+            }
+        }
+        let total = grams.values().sum::<f32>();
+        let log_probs = grams
+            .into_iter()
+            .filter(|(_, count)| *count > 0.0)
+            .map(|(gram, count)| (gram, (count / total).log10()))
+            .collect::<HashMap<_, _>>();
+        let boundary = log_probs.values().map(|lp| 10f32.powf(*lp) * lp).sum();
+        let rarest = log_probs.values().copied().fold(0.0, f32::min);
+        Self {
+            n,
+            log_probs,
+            floor: rarest - 1.0,
+            boundary,
+        }
+    }
+
+    /// Most frequent English bigrams, counts are in percents of all bigrams.
+    pub fn english_bigrams() -> Self {
+        Self::new(2, ENGLISH_BIGRAMS.iter().copied())
+    }
+
+    /// Most frequent English trigrams, counts are in percents of all trigrams.
+    pub fn english_trigrams() -> Self {
+        Self::new(3, ENGLISH_TRIGRAMS.iter().copied())
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    fn window_score(&self, window: &[u8]) -> f32 {
+        if window.iter().all(|&b| is_letter_byte(b)) {
+            let gram = window.to_ascii_lowercase();
+            self.log_probs.get(&gram).copied().unwrap_or(self.floor)
+        } else if window
+            .iter()
+            .all(|&b| is_letter_byte(b) || is_word_separator(b))
+        {
+            self.boundary
+        } else {
+            self.floor
+        }
+    }
+}
+
+impl PlaintextScorer for NgramModel {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        if plaintext.len() < self.n {
+            return self.window_score(plaintext);
+        }
+        let windows = plaintext.len() - self.n + 1;
+        plaintext
+            .windows(self.n)
+            .map(|window| self.window_score(window))
+            .sum::<f32>()
+            / windows as f32
+    }
+}
+
+pub(crate) const ENGLISH_BIGRAMS: [(&str, f32); 40] = [
+    ("th", 3.56),
+    ("he", 3.07),
+    ("in", 2.43),
+    ("er", 2.05),
+    ("an", 1.99),
+    ("re", 1.85),
+    ("on", 1.76),
+    ("at", 1.49),
+    ("en", 1.45),
+    ("nd", 1.35),
+    ("ti", 1.34),
+    ("es", 1.34),
+    ("or", 1.28),
+    ("te", 1.20),
+    ("of", 1.17),
+    ("ed", 1.17),
+    ("is", 1.13),
+    ("it", 1.12),
+    ("al", 1.09),
+    ("ar", 1.07),
+    ("st", 1.05),
+    ("to", 1.04),
+    ("nt", 1.04),
+    ("ng", 0.95),
+    ("se", 0.93),
+    ("ha", 0.93),
+    ("as", 0.87),
+    ("ou", 0.87),
+    ("io", 0.83),
+    ("le", 0.83),
+    ("ve", 0.83),
+    ("co", 0.79),
+    ("me", 0.79),
+    ("de", 0.76),
+    ("hi", 0.76),
+    ("ri", 0.73),
+    ("ro", 0.73),
+    ("ic", 0.70),
+    ("ne", 0.69),
+    ("ea", 0.69),
+];
+
+pub(crate) const ENGLISH_TRIGRAMS: [(&str, f32); 30] = [
+    ("the", 1.81),
+    ("and", 0.73),
+    ("ing", 0.72),
+    ("ent", 0.42),
+    ("ion", 0.42),
+    ("her", 0.36),
+    ("for", 0.34),
+    ("tha", 0.33),
+    ("nth", 0.33),
+    ("int", 0.32),
+    ("ere", 0.31),
+    ("tio", 0.31),
+    ("ter", 0.30),
+    ("est", 0.28),
+    ("ers", 0.28),
+    ("ati", 0.26),
+    ("hat", 0.26),
+    ("ate", 0.25),
+    ("all", 0.25),
+    ("eth", 0.24),
+    ("hes", 0.24),
+    ("ver", 0.24),
+    ("his", 0.24),
+    ("oft", 0.22),
+    ("ith", 0.21),
+    ("fth", 0.21),
+    ("sth", 0.21),
+    ("oth", 0.21),
+    ("res", 0.21),
+    ("ont", 0.20),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_3::brute_single_byte_xor_with;
+
+    fn xor(data: &[u8], key: u8) -> Vec<u8> {
+        data.iter().map(|b| b ^ key).collect()
+    }
+
+    #[test]
+    fn scorers_should_pass() {
+        let english = b"The quick brown fox jumps over the lazy dog.";
+        let flipped = xor(english, 0x20);
+        let scorers: [Box<dyn PlaintextScorer>; 3] = [
+            Box::new(ChiSquared::english()),
+            Box::new(NgramModel::english_bigrams()),
+            Box::new(NgramModel::english_trigrams()),
+        ];
+        for scorer in scorers.iter() {
+            assert!(scorer.score(english) > scorer.score(&flipped));
+            assert!(scorer.score(english) > scorer.score(&xor(english, 0x01)));
+        }
+        assert_eq!(Printable::Ascii.score(english), 0.0);
+        assert_eq!(Printable::Ascii.score(&flipped), f32::NEG_INFINITY);
+        assert_eq!(
+            Printable::Ascii.score("naïve".as_bytes()),
+            f32::NEG_INFINITY
+        );
+        assert_eq!(Printable::Utf8.score("naïve".as_bytes()), 0.0);
+        assert_eq!(Printable::Utf8.score(&[0xff, 0x41]), f32::NEG_INFINITY);
+        assert_eq!(
+            (Printable::Ascii, FrequencySum).score(&flipped),
+            f32::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn histogram_scorers_should_pass() {
+        let english = b"The quick brown fox jumps over the lazy dog.";
+        let histogram = byte_histogram(xor(english, 0x3c));
+        let close = |a: f32, b: f32| a == b || (a - b).abs() <= 1e-3 * a.abs().max(1.0);
+        for key in [0x00, 0x3c, 0x1c, 0xff] {
+            let plaintext = xor(&xor(english, 0x3c), key);
+            let chi = ChiSquared::english();
+            assert!(close(
+                chi.score_histogram(&histogram, key),
+                chi.score(&plaintext)
+            ));
+            assert!(close(
+                FrequencySum.score_histogram(&histogram, key),
+                FrequencySum.score(&plaintext)
+            ));
+            assert_eq!(
+                Printable::Ascii.score_histogram(&histogram, key),
+                Printable::Ascii.score(&plaintext)
+            );
+        }
+    }
+
+    #[test]
+    fn mixed_case_single_byte_xor_should_pass() {
+        let plaintext = b"ATTACK AT DAWN, HOLD THE LINE";
+        let cipher = xor(plaintext, 0x4b);
+        for scorer in [
+            &(Printable::Ascii, ChiSquared::english()) as &dyn PlaintextScorer,
+            &(Printable::Ascii, NgramModel::english_bigrams()),
+            &(Printable::Ascii, NgramModel::english_trigrams()),
+        ] {
+            let (key, guess, _) = brute_single_byte_xor_with(&cipher, scorer).unwrap();
+            assert_eq!((key, guess.as_slice()), (0x4b, &plaintext[..]));
+        }
+        // unigram sum ignores uppercase letters and picks the case-flipped guess
+        let (key, ..) = brute_single_byte_xor_with(&cipher, &FrequencySum).unwrap();
+        assert_ne!(key, 0x4b);
+    }
+}
@@ -1,46 +1,512 @@
 /// Task: base64 encoding/decoding implementation
 use core::ops::{Add, Rem, Sub, SubAssign};
-use lazy_static::lazy_static;
 use num::traits::One;
 use std::fmt::Display;
 
-#[derive(Debug, Clone)]
+/// Decoding errors, `offset` is a byte offset of the offending char in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Base64Error {
     /// Provided base64 input is invalid, i.e. it contains chars outside of base64 range.
-    ParsingFailed,
-    /// Input base64 string is malformed, i.e. size is not a multiple of 4.
-    AlignmentMismatch,
+    ParsingFailed { offset: usize, ch: char },
+    /// Input base64 string is malformed, i.e. it ends with an incomplete group
+    /// which starts at `offset`.
+    AlignmentMismatch { offset: usize },
+    /// Last char of a partial group has bits set which don't belong to any decoded byte.
+    NonCanonicalTrailingBits { offset: usize, ch: char },
+    /// Padding is misplaced, incomplete or not allowed by the config.
+    InvalidPadding { offset: usize, ch: char },
+    /// Input continues after a padded group, reported only in strict mode.
+    TrailingData { offset: usize, ch: char },
+}
+
+impl Base64Error {
+    pub fn offset(&self) -> usize {
+        match *self {
+            Base64Error::ParsingFailed { offset, .. }
+            | Base64Error::AlignmentMismatch { offset }
+            | Base64Error::NonCanonicalTrailingBits { offset, .. }
+            | Base64Error::InvalidPadding { offset, .. }
+            | Base64Error::TrailingData { offset, .. } => offset,
+        }
+    }
+
+    fn with_char(self, new_ch: char) -> Self {
+        match self {
+            Base64Error::ParsingFailed { offset, .. } => {
+                Base64Error::ParsingFailed { offset, ch: new_ch }
+            }
+            Base64Error::TrailingData { offset, .. } => {
+                Base64Error::TrailingData { offset, ch: new_ch }
+            }
+            other => other,
+        }
+    }
 }
 
 impl Display for Base64Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
-            Base64Error::ParsingFailed => write!(f, "input contains chars outside base64 range"),
-            Base64Error::AlignmentMismatch => write!(f, "input size is not multiple of 4"),
+            Base64Error::ParsingFailed { offset, ch } => {
+                write!(
+                    f,
+                    "char {:?} at offset {} is outside base64 range",
+                    ch, offset
+                )
+            }
+            Base64Error::AlignmentMismatch { offset } => {
+                write!(f, "incomplete group at offset {}", offset)
+            }
+            Base64Error::NonCanonicalTrailingBits { offset, ch } => {
+                write!(
+                    f,
+                    "char {:?} at offset {} has non-zero trailing bits",
+                    ch, offset
+                )
+            }
+            Base64Error::InvalidPadding { offset, ch } => {
+                write!(f, "invalid padding {:?} at offset {}", ch, offset)
+            }
+            Base64Error::TrailingData { offset, ch } => {
+                write!(f, "char {:?} at offset {} follows padding", ch, offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Base64Error {}
+
+/// Set of chars used to represent 6-bit values, alphabets differ only in the last two chars.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base64Alphabet {
+    /// RFC 4648 `+/` alphabet.
+    Standard,
+    /// RFC 4648 `-_` alphabet, safe for URLs and filenames.
+    UrlSafe,
+}
+
+const STANDARD_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Marks bytes outside of alphabet in decode tables, has high bits set unlike any sextet.
+const INVALID_INDEX: u8 = 0xff;
+
+const fn decode_table(chars: &[u8; 64]) -> [u8; 256] {
+    let mut table = [INVALID_INDEX; 256];
+    let mut i = 0;
+    while i < 64 {
+        table[chars[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+const STANDARD_TABLE: [u8; 256] = decode_table(STANDARD_CHARS);
+const URL_SAFE_TABLE: [u8; 256] = decode_table(URL_SAFE_CHARS);
+
+impl Base64Alphabet {
+    pub fn chars(self) -> &'static [u8; 64] {
+        match self {
+            Base64Alphabet::Standard => STANDARD_CHARS,
+            Base64Alphabet::UrlSafe => URL_SAFE_CHARS,
         }
     }
+
+    /// Maps every byte to its sextet, bytes outside of alphabet are mapped to `0xff`.
+    pub fn decode_table(self) -> &'static [u8; 256] {
+        match self {
+            Base64Alphabet::Standard => &STANDARD_TABLE,
+            Base64Alphabet::UrlSafe => &URL_SAFE_TABLE,
+        }
+    }
+
+    fn index(self, input: u8) -> Option<u8> {
+        let idx = self.decode_table()[input as usize];
+        (idx != INVALID_INDEX).then_some(idx)
+    }
+}
+
+/// How `=` padding is treated when decoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base64Padding {
+    /// Input must be padded to a multiple of 4 chars.
+    Required,
+    /// Input must not contain padding.
+    Forbidden,
+    /// Both padded and unpadded input is accepted.
+    Indifferent,
+}
+
+/// Line wrapping of encoded output, e.g. 76 columns with CRLF for MIME.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Base64LineWrap {
+    pub width: usize,
+    pub line_ending: &'static str,
 }
 
-lazy_static! {
-    static ref BASE64_CHARS: [char; 64] = {
-        let chars = ('A'..='Z')
-            .chain('a'..='z')
-            .chain('0'..='9')
-            .chain(['+', '/'].into_iter());
-        chars.collect::<Vec<_>>().try_into().unwrap()
+/// Describes a base64 flavour, use one of the predefined configs or tweak them with
+/// struct update syntax, e.g. `Base64Config { ignore_whitespace: true, ..Base64Config::STANDARD }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Base64Config {
+    pub alphabet: Base64Alphabet,
+    /// Append `=` padding when encoding.
+    pub pad: bool,
+    pub decode_padding: Base64Padding,
+    /// Wrap encoded output into lines, line breaks are skipped when decoding.
+    pub line_wrap: Option<Base64LineWrap>,
+    /// Skip any ASCII whitespace when decoding.
+    pub ignore_whitespace: bool,
+    /// Accept only canonical encodings: trailing bits must be zero and nothing
+    /// may follow a padded group.
+    pub strict: bool,
+}
+
+impl Base64Config {
+    pub const STANDARD: Base64Config = Base64Config {
+        alphabet: Base64Alphabet::Standard,
+        pad: true,
+        decode_padding: Base64Padding::Required,
+        line_wrap: None,
+        ignore_whitespace: false,
+        strict: false,
+    };
+    pub const STANDARD_NO_PAD: Base64Config = Base64Config {
+        pad: false,
+        decode_padding: Base64Padding::Indifferent,
+        ..Base64Config::STANDARD
+    };
+    pub const URL_SAFE: Base64Config = Base64Config {
+        alphabet: Base64Alphabet::UrlSafe,
+        ..Base64Config::STANDARD
+    };
+    pub const URL_SAFE_NO_PAD: Base64Config = Base64Config {
+        alphabet: Base64Alphabet::UrlSafe,
+        ..Base64Config::STANDARD_NO_PAD
     };
+    /// RFC 2045: 76-column lines separated with CRLF, whitespace is ignored when decoding.
+    pub const MIME: Base64Config = Base64Config {
+        line_wrap: Some(Base64LineWrap {
+            width: 76,
+            line_ending: "\r\n",
+        }),
+        ignore_whitespace: true,
+        ..Base64Config::STANDARD
+    };
+
+    pub fn encode<T: AsRef<[u8]>>(&self, input_data: T) -> String {
+        let src_ref = input_data.as_ref();
+        let mut result = vec![0u8; self.encoded_len(src_ref.len())];
+        self.encode_to_slice(src_ref, &mut result);
+        let result = String::from_utf8(result).expect("base64 alphabet is ASCII");
+        match self.line_wrap {
+            Some(wrap) if wrap.width > 0 => wrap_lines(&result, wrap),
+            _ => result,
+        }
+    }
+
+    /// Length of encoded data, line wrapping is not taken into account.
+    pub fn encoded_len(&self, input_len: usize) -> usize {
+        if self.pad {
+            input_len.div_ceil(3) * 4
+        } else {
+            (input_len * 4).div_ceil(3)
+        }
+    }
+
+    /// Upper bound of decoded length, exact for padded input without skipped chars.
+    pub fn decoded_len_estimate(input_len: usize) -> usize {
+        input_len.div_ceil(4) * 3
+    }
+
+    /// Encodes without line wrapping, returns number of bytes written.
+    ///
+    /// Panics if `output` is shorter than [`Base64Config::encoded_len`].
+    pub fn encode_to_slice(&self, input: &[u8], output: &mut [u8]) -> usize {
+        let chars = self.alphabet.chars();
+        let len = self.encoded_len(input.len());
+        assert!(output.len() >= len, "Output is too short for encoded data");
+        let encode_group = |group: &[u8], out: &mut [u8]| {
+            let value = (group[0] as u32) << 16 | (group[1] as u32) << 8 | group[2] as u32;
+            out[0] = chars[(value >> 18) as usize & 0x3f];
+            out[1] = chars[(value >> 12) as usize & 0x3f];
+            out[2] = chars[(value >> 6) as usize & 0x3f];
+            out[3] = chars[value as usize & 0x3f];
+        };
+        let input_chunks = input.chunks_exact(ENCODE_CHUNK);
+        let tail = input_chunks.remainder();
+        let mut written = 0;
+        for in_chunk in input_chunks {
+            let out_chunk = &mut output[written..written + ENCODE_CHUNK / 3 * 4];
+            for (group, out) in in_chunk.chunks_exact(3).zip(out_chunk.chunks_exact_mut(4)) {
+                encode_group(group, out);
+            }
+            written += ENCODE_CHUNK / 3 * 4;
+        }
+        for chunk in tail.chunks(3) {
+            let mut group = [0u8; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let mut out = [b'='; 4];
+            encode_group(&group, &mut out);
+            let used = if self.pad { 4 } else { chunk.len() + 1 };
+            out[chunk.len() + 1..].fill(b'=');
+            output[written..written + used].copy_from_slice(&out[..used]);
+            written += used;
+        }
+        written
+    }
+
+    /// Decodes groups of 4 chars, unless `strict` is set a padded group may be followed
+    /// by another group, so concatenated encodings are accepted.
+    pub fn decode(&self, input_base64: &str) -> Result<Vec<u8>, Base64Error> {
+        let mut result = Vec::with_capacity(Self::decoded_len_estimate(input_base64.len()));
+        let mut decoder = Base64Decoder::new(*self);
+        decoder
+            .feed(input_base64.as_bytes(), &mut result)
+            .and_then(|_| decoder.finish(&mut result))
+            .map_err(|err| match input_base64[err.offset()..].chars().next() {
+                // decoder sees only bytes, report the whole char instead
+                Some(ch) if !ch.is_ascii() => err.with_char(ch),
+                _ => err,
+            })?;
+        Ok(result)
+    }
+
+    /// Decodes into `output`, returns number of bytes written.
+    ///
+    /// Panics if `output` is shorter than [`Base64Config::decoded_len_estimate`].
+    pub fn decode_to_slice(&self, input: &[u8], output: &mut [u8]) -> Result<usize, Base64Error> {
+        let mut decoder = Base64Decoder::new(*self);
+        let written = decoder.feed_to_slice(input, output)?;
+        Ok(written + decoder.finish_to_slice(&mut output[written..])?)
+    }
+}
+
+/// Input bytes encoded per iteration of the fast path, decoder uses matching 32-char chunks.
+const ENCODE_CHUNK: usize = 24;
+const DECODE_CHUNK: usize = ENCODE_CHUNK / 3 * 4;
+
+/// Decodes a chunk without any padding or skipped chars, returns `false` if it has any.
+fn decode_chunk(chunk: &[u8], table: &[u8; 256], output: &mut [u8]) -> bool {
+    let mut sextets = [0u8; DECODE_CHUNK];
+    let mut acc = 0;
+    for (sextet, &b) in sextets.iter_mut().zip(chunk.iter()) {
+        *sextet = table[b as usize];
+        acc |= *sextet;
+    }
+    if acc & 0xc0 != 0 {
+        return false;
+    }
+    for (group, out) in sextets.chunks_exact(4).zip(output.chunks_exact_mut(3)) {
+        let value = (group[0] as u32) << 18
+            | (group[1] as u32) << 12
+            | (group[2] as u32) << 6
+            | group[3] as u32;
+        out.copy_from_slice(&value.to_be_bytes()[1..]);
+    }
+    true
+}
+
+/// Incremental decoder, input may be fed in arbitrary pieces.
+pub struct Base64Decoder {
+    config: Base64Config,
+    /// Skip line breaks even if config has no line wrapping.
+    skip_line_breaks: bool,
+    group: [u8; 4],
+    filled: usize,
+    padding: usize,
+    /// Offset of the first char of current group.
+    group_offset: usize,
+    /// Offset of the last data char and first pad char of current group.
+    last_offset: usize,
+    pad_offset: usize,
+    /// Whether any complete group so far was padded.
+    padded: bool,
+    offset: usize,
+}
+
+impl Base64Decoder {
+    pub fn new(config: Base64Config) -> Self {
+        Self {
+            config,
+            skip_line_breaks: config.line_wrap.is_some(),
+            group: [0; 4],
+            filled: 0,
+            padding: 0,
+            group_offset: 0,
+            last_offset: 0,
+            pad_offset: 0,
+            padded: false,
+            offset: 0,
+        }
+    }
+
+    /// Skips line breaks even if config has no line wrapping, e.g. for line-split files.
+    pub fn skip_line_breaks(mut self, skip: bool) -> Self {
+        self.skip_line_breaks = skip;
+        self
+    }
+
+    /// Decodes all complete groups, the rest is kept until next call.
+    pub fn feed(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), Base64Error> {
+        let start = output.len();
+        output.resize(start + self.feed_len_bound(input.len()), 0);
+        let result = self.feed_to_slice(input, &mut output[start..]);
+        output.truncate(start + *result.as_ref().unwrap_or(&0));
+        result.map(|_| ())
+    }
+
+    /// Decodes the last group, which may be incomplete if padding isn't required.
+    pub fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), Base64Error> {
+        let mut tail = [0u8; 3];
+        let count = self.finish_to_slice(&mut tail)?;
+        output.extend_from_slice(&tail[..count]);
+        Ok(())
+    }
+
+    /// Upper bound of bytes produced by feeding `input_len` more bytes.
+    pub fn feed_len_bound(&self, input_len: usize) -> usize {
+        (self.filled + self.padding + input_len) / 4 * 3
+    }
+
+    /// Same as [`Base64Decoder::feed`], returns number of bytes written.
+    ///
+    /// Panics if `output` is shorter than [`Base64Decoder::feed_len_bound`].
+    pub fn feed_to_slice(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, Base64Error> {
+        assert!(
+            output.len() >= self.feed_len_bound(input.len()),
+            "Output is too short for decoded data"
+        );
+        let table = self.config.alphabet.decode_table();
+        let mut written = 0;
+        let mut pos = 0;
+        while pos < input.len() {
+            let fast_path = self.filled + self.padding == 0 && !(self.config.strict && self.padded);
+            if fast_path {
+                while let Some(chunk) = input.get(pos..pos + DECODE_CHUNK) {
+                    let out = &mut output[written..written + ENCODE_CHUNK];
+                    if !decode_chunk(chunk, table, out) {
+                        break;
+                    }
+                    pos += DECODE_CHUNK;
+                    written += ENCODE_CHUNK;
+                    self.offset += DECODE_CHUNK;
+                }
+                if pos == input.len() {
+                    break;
+                }
+            }
+            written += self.feed_byte(input[pos], &mut output[written..])?;
+            pos += 1;
+        }
+        Ok(written)
+    }
+
+    /// Same as [`Base64Decoder::finish`], `output` must fit 2 bytes.
+    pub fn finish_to_slice(&mut self, output: &mut [u8]) -> Result<usize, Base64Error> {
+        if self.filled + self.padding == 0 {
+            return Ok(0);
+        }
+        if self.padding > 0 {
+            return Err(Base64Error::InvalidPadding {
+                offset: self.pad_offset,
+                ch: '=',
+            });
+        }
+        if self.filled < 2 || self.config.decode_padding == Base64Padding::Required {
+            return Err(Base64Error::AlignmentMismatch {
+                offset: self.group_offset,
+            });
+        }
+        self.push_group(output)
+    }
+
+    /// Processes a single byte, returns number of decoded bytes written.
+    fn feed_byte(&mut self, b: u8, output: &mut [u8]) -> Result<usize, Base64Error> {
+        let offset = self.offset;
+        self.offset += 1;
+        if self.is_skipped(b) {
+            return Ok(0);
+        }
+        if self.filled + self.padding == 0 {
+            self.group_offset = offset;
+        }
+        if b == b'=' {
+            if self.config.decode_padding == Base64Padding::Forbidden || self.filled < 2 {
+                return Err(Base64Error::InvalidPadding { offset, ch: '=' });
+            }
+            if self.padding == 0 {
+                self.pad_offset = offset;
+            }
+            self.padding += 1;
+        } else {
+            let ch = b as char;
+            if self.padding > 0 {
+                return Err(Base64Error::InvalidPadding {
+                    offset: self.pad_offset,
+                    ch: '=',
+                });
+            }
+            if self.config.strict && self.padded {
+                return Err(Base64Error::TrailingData { offset, ch });
+            }
+            let idx = self
+                .config
+                .alphabet
+                .index(b)
+                .ok_or(Base64Error::ParsingFailed { offset, ch })?;
+            self.group[self.filled] = idx;
+            self.filled += 1;
+            self.last_offset = offset;
+        }
+        if self.filled + self.padding == 4 {
+            self.padded |= self.padding > 0;
+            return self.push_group(output);
+        }
+        Ok(0)
+    }
+
+    /// Writes bytes encoded by the filled part of the group, trailing bits are dropped.
+    fn push_group(&mut self, output: &mut [u8]) -> Result<usize, Base64Error> {
+        let group = &self.group;
+        let trailing_bits = match self.filled {
+            2 => group[1] & 0x0f,
+            3 => group[2] & 0x03,
+            _ => 0,
+        };
+        if self.config.strict && trailing_bits != 0 {
+            let offset = self.last_offset;
+            let ch = self.config.alphabet.chars()[group[self.filled - 1] as usize] as char;
+            return Err(Base64Error::NonCanonicalTrailingBits { offset, ch });
+        }
+        let bytes = [
+            group[0] << 2 | group[1] >> 4,
+            group[1] << 4 | group[2] >> 2,
+            group[2] << 6 | group[3],
+        ];
+        let count = self.filled - 1;
+        output[..count].copy_from_slice(&bytes[..count]);
+        self.filled = 0;
+        self.padding = 0;
+        Ok(count)
+    }
+
+    fn is_skipped(&self, b: u8) -> bool {
+        (self.config.ignore_whitespace && b.is_ascii_whitespace())
+            || (self.skip_line_breaks && (b == b'\r' || b == b'\n'))
+    }
 }
 
-fn base64_to_index(input: char) -> Result<u8, Base64Error> {
-    let char_byte: u8 = input.try_into().map_err(|_| Base64Error::ParsingFailed)?;
-    match input {
-        '+' => Ok(62),
-        '/' => Ok(63),
-        '0'..='9' => Ok(char_byte - 48 + 52),
-        'A'..='Z' => Ok(char_byte - 65),
-        'a'..='z' => Ok(char_byte - 97 + 26),
-        _ => Err(Base64Error::ParsingFailed),
+fn wrap_lines(encoded: &str, wrap: Base64LineWrap) -> String {
+    let mut result =
+        String::with_capacity(encoded.len() + encoded.len() / wrap.width * wrap.line_ending.len());
+    for (i, line) in encoded.as_bytes().chunks(wrap.width).enumerate() {
+        if i > 0 {
+            result.push_str(wrap.line_ending);
+        }
+        result.extend(line.iter().map(|&b| b as char));
     }
+    result
 }
 
 pub fn hex_to_base64(input_hex: &str) -> Result<String, hex::FromHexError> {
@@ -64,78 +530,11 @@ pub fn next_multiple<
 }
 
 pub fn bytes_to_base64<T: AsRef<[u8]>>(input_data: T) -> String {
-    let src_ref = input_data.as_ref();
-    let mut result = Vec::new();
-    result.reserve(next_multiple(src_ref.len(), 3));
-    let mut it = src_ref.iter();
-    loop {
-        let it_0 = it.next();
-        let it_1 = it.next();
-        let it_2 = it.next();
-        if it_0.is_none() {
-            break;
-        }
-        let comp_0 = it_0.map(|ok| ok >> 2);
-        let comp_1 = it_0.map(|ok| 0x3f & ok << 4 | it_1.map(|v| v >> 4).unwrap_or(0));
-        let comp_2 = it_1.map(|ok| 0x3f & ok << 2 | it_2.map(|v| v >> 6).unwrap_or(0));
-        let comp_3 = it_2.map(|ok| 0x3f & ok);
-        result.extend(
-            vec![comp_0, comp_1, comp_2, comp_3]
-                .into_iter()
-                .map(|comp| comp.map(|idx| BASE64_CHARS[idx as usize]).unwrap_or('=')),
-        );
-        if it_2.is_none() {
-            break;
-        }
-    }
-
-    result.into_iter().collect()
+    Base64Config::STANDARD.encode(input_data)
 }
+
 pub fn base64_to_bytes(input_base64: &str) -> Result<Vec<u8>, Base64Error> {
-    let mut it = input_base64.chars();
-    let mut result = Vec::new();
-    while let Some(it_in) = it.next() {
-        let it_0 = Some(base64_to_index(it_in)?);
-        macro_rules! proc_it {
-            ($($i: ident), *) => {
-                $(
-                    let $i = it.next().ok_or(Base64Error::AlignmentMismatch)?;
-                    let $i = if $i == '=' {
-                        None
-                    } else {
-                        Some(base64_to_index($i)?)
-                    };
-                )*
-            }
-        }
-        proc_it!(it_1, it_2, it_3);
-        let comp_0 = it_0.map(|ok| ok << 2 | it_1.map(|v| v >> 4).unwrap_or(0));
-        let mut comp_1 = it_1.map(|ok| ok << 4 | it_2.map(|v| v >> 2).unwrap_or(0));
-        let mut comp_2 = it_2.map(|ok| ok << 6 | it_3.unwrap_or(0));
-        if let Some(comp) = comp_1 {
-            if comp == 0 && it_2.is_none() {
-                comp_1 = None
-            }
-        };
-        if let Some(comp) = comp_2 {
-            if comp == 0 && it_3.is_none() {
-                comp_2 = None
-            }
-        };
-        macro_rules! proc_comp {
-            ($($i: ident),*) => {
-                $(
-                    if let Some(comp) = $i {
-                        result.push(comp);
-                    } else {
-                        break;
-                    }
-                )*
-            }
-        }
-        proc_comp!(comp_0, comp_1, comp_2);
-    }
-    Ok(result)
+    Base64Config::STANDARD.decode(input_base64)
 }
 
 #[cfg(test)]
@@ -167,4 +566,196 @@ mod tests {
         assert_eq!(base64_to_hex("SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t").unwrap(),
             "49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d");
     }
+
+    #[test]
+    fn base64_configs_should_pass() {
+        let data = [0xfbu8, 0xff, 0xbf, 0xfe];
+        assert_eq!(Base64Config::STANDARD.encode(data), "+/+//g==");
+        assert_eq!(Base64Config::STANDARD_NO_PAD.encode(data), "+/+//g");
+        assert_eq!(Base64Config::URL_SAFE.encode(data), "-_-__g==");
+        assert_eq!(Base64Config::URL_SAFE_NO_PAD.encode(data), "-_-__g");
+
+        assert_eq!(
+            Base64Config::URL_SAFE_NO_PAD.decode("-_-__g").unwrap(),
+            data
+        );
+        assert_eq!(
+            Base64Config::URL_SAFE_NO_PAD.decode("-_-__g==").unwrap(),
+            data
+        );
+        assert!(Base64Config::URL_SAFE_NO_PAD.decode("+/+//g").is_err());
+        assert!(Base64Config::STANDARD.decode("+/+//g").is_err());
+        let forbidden = Base64Config {
+            decode_padding: Base64Padding::Forbidden,
+            ..Base64Config::STANDARD_NO_PAD
+        };
+        assert!(forbidden.decode("+/+//g==").is_err());
+        assert!(Base64Config::STANDARD_NO_PAD.decode("+/+//").is_err());
+        assert!(Base64Config::STANDARD_NO_PAD.decode("+/+//g=").is_err());
+
+        let lenient = Base64Config {
+            ignore_whitespace: true,
+            ..Base64Config::STANDARD
+        };
+        assert_eq!(lenient.decode(" Zm9v\tYm\r\nFy ").unwrap(), b"foobar");
+        assert!(Base64Config::STANDARD.decode("Zm9v Ym").is_err());
+        assert_eq!(Base64Config::STANDARD.decode("Zg==Zm9v").unwrap(), b"ffoo");
+    }
+
+    #[test]
+    fn base64_mime_should_pass() {
+        let data = vec![0u8; 60];
+        let encoded = Base64Config::MIME.encode(&data);
+        let lines = encoded.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "A".repeat(76));
+        assert_eq!(lines[1], "AAAA");
+        assert_eq!(Base64Config::MIME.decode(&encoded).unwrap(), data);
+        assert_eq!(Base64Config::MIME.encode(&data[..57]), "A".repeat(76));
+    }
+
+    #[test]
+    fn base64_error_positions_should_pass() {
+        let std = Base64Config::STANDARD;
+        assert_eq!(
+            std.decode("Zm9v\nYg=="),
+            Err(Base64Error::ParsingFailed {
+                offset: 4,
+                ch: '\n'
+            })
+        );
+        assert_eq!(
+            std.decode("Zm9vYé=="),
+            Err(Base64Error::ParsingFailed {
+                offset: 5, ch: 'é'
+            })
+        );
+        assert_eq!(
+            std.decode("Zm9vYmE"),
+            Err(Base64Error::AlignmentMismatch { offset: 4 })
+        );
+        assert_eq!(
+            std.decode("Zm9vY==="),
+            Err(Base64Error::InvalidPadding { offset: 5, ch: '=' })
+        );
+        assert_eq!(
+            std.decode("Zm9vYg=a"),
+            Err(Base64Error::InvalidPadding { offset: 6, ch: '=' })
+        );
+        assert_eq!(
+            std.decode("Zm9vYg="),
+            Err(Base64Error::InvalidPadding { offset: 6, ch: '=' })
+        );
+        assert_eq!(
+            Base64Config::MIME.decode("Zm9v\r\n!"),
+            Err(Base64Error::ParsingFailed { offset: 6, ch: '!' })
+        );
+    }
+
+    #[test]
+    fn base64_strict_should_pass() {
+        let strict = Base64Config {
+            strict: true,
+            ..Base64Config::STANDARD
+        };
+        // lenient mode drops non-zero trailing bits and accepts concatenated groups
+        assert_eq!(base64_to_bytes("Zh==").unwrap(), b"f");
+        assert_eq!(base64_to_bytes("Zm9=").unwrap(), b"fo");
+        assert_eq!(base64_to_bytes("Zg==Zg==").unwrap(), b"ff");
+
+        assert_eq!(strict.decode("Zm9vYmFy").unwrap(), b"foobar");
+        assert_eq!(strict.decode("Zm8=").unwrap(), b"fo");
+        assert_eq!(
+            strict.decode("Zh=="),
+            Err(Base64Error::NonCanonicalTrailingBits { offset: 1, ch: 'h' })
+        );
+        assert_eq!(
+            strict.decode("Zm9vZm9="),
+            Err(Base64Error::NonCanonicalTrailingBits { offset: 6, ch: '9' })
+        );
+        assert_eq!(
+            strict.decode("Zg==Zg=="),
+            Err(Base64Error::TrailingData { offset: 4, ch: 'Z' })
+        );
+        let strict_no_pad = Base64Config {
+            strict: true,
+            ..Base64Config::URL_SAFE_NO_PAD
+        };
+        assert_eq!(strict_no_pad.decode("Zm8").unwrap(), b"fo");
+        assert_eq!(
+            strict_no_pad.decode("Zm9"),
+            Err(Base64Error::NonCanonicalTrailingBits { offset: 2, ch: '9' })
+        );
+    }
+
+    #[test]
+    fn base64_slices_should_pass() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+            (
+                "I'm killing your brain like a poisonous mushroom",
+                "SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t",
+            ),
+        ];
+        let config = Base64Config::STANDARD;
+        for (data, encoded) in vectors {
+            let mut output = vec![0u8; config.encoded_len(data.len())];
+            assert_eq!(
+                config.encode_to_slice(data.as_bytes(), &mut output),
+                encoded.len()
+            );
+            assert_eq!(output, encoded.as_bytes());
+
+            let mut output = vec![0u8; Base64Config::decoded_len_estimate(encoded.len())];
+            let written = config
+                .decode_to_slice(encoded.as_bytes(), &mut output)
+                .unwrap();
+            assert_eq!(&output[..written], data.as_bytes());
+        }
+
+        // long inputs go through the chunked path, compare it with per-byte decoding
+        let data = (0..1000u32)
+            .map(|i| (i * 7919 % 251) as u8)
+            .collect::<Vec<_>>();
+        for config in [
+            Base64Config::STANDARD,
+            Base64Config::URL_SAFE_NO_PAD,
+            Base64Config::MIME,
+        ] {
+            for len in [0, 1, 2, 23, 24, 25, 47, 48, 49, 100, 1000] {
+                let encoded = config.encode(&data[..len]);
+                let mut bytewise = Vec::new();
+                let mut decoder = Base64Decoder::new(config);
+                for b in encoded.bytes() {
+                    decoder.feed(&[b], &mut bytewise).unwrap();
+                }
+                decoder.finish(&mut bytewise).unwrap();
+                assert_eq!(bytewise, &data[..len]);
+                assert_eq!(config.decode(&encoded).unwrap(), &data[..len]);
+            }
+        }
+        let mut decoded = Vec::new();
+        let mut decoder = Base64Decoder::new(Base64Config::STANDARD).skip_line_breaks(true);
+        decoder.feed(b"TWFu\r\nTWE=\n", &mut decoded).unwrap();
+        decoder.finish(&mut decoded).unwrap();
+        assert_eq!(decoded, b"ManMa");
+        let mut decoder = Base64Decoder::new(Base64Config::STANDARD);
+        assert!(decoder.feed(b"TWFu\nTWE=", &mut Vec::new()).is_err());
+
+        let mut encoded = bytes_to_base64(&data[..96]);
+        encoded.replace_range(70..71, "!");
+        assert_eq!(
+            base64_to_bytes(&encoded),
+            Err(Base64Error::ParsingFailed {
+                offset: 70,
+                ch: '!'
+            })
+        );
+    }
 }
@@ -1,5 +1,4 @@
 /// Task: Fixed XOR
-
 pub fn fixed_length_xor(bytes_a: &[u8], bytes_b: &[u8]) -> Result<Vec<u8>, String> {
     if bytes_a.len() != bytes_b.len() {
         return Err("Input slices must be of same length".to_owned());
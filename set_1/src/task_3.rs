@@ -1,4 +1,6 @@
 /// Task: Brute single-byte XOR
+use crate::language::LanguageModel;
+use crate::scoring::{FrequencySum, PlaintextScorer};
 use lazy_static::lazy_static;
 
 use std::cmp::Ordering;
@@ -22,7 +24,7 @@ lazy_static! {
             ('m', 0.0202124),
             ('n', 0.0564513),
             ('o', 0.0596302),
-            ('p', 0.0596302),
+            ('p', 0.0137645),
             ('q', 0.0008606),
             ('r', 0.0497563),
             ('s', 0.0515760),
@@ -48,18 +50,93 @@ pub fn rate_english_frequency<T: AsRef<[u8]>>(guess: T) -> f32 {
         .sum()
 }
 
+/// Same as `rate_english_frequency`, but weights are taken from the model.
+pub fn rate_frequency<T: AsRef<[u8]>>(guess: T, model: &LanguageModel) -> f32 {
+    guess.as_ref().iter().map(|&b| model.frequency(b)).sum()
+}
+
 // ONLY WORKS FOR ASCII INPUT
 pub fn brute_single_byte_xor<T: AsRef<[u8]>>(data: T) -> Option<(u8, Vec<u8>, f32)> {
+    brute_single_byte_xor_with(data, &FrequencySum)
+}
+
+/// Same as `brute_single_byte_xor`, but candidates are rated by the given scorer.
+pub fn brute_single_byte_xor_with<T, S>(data: T, scorer: &S) -> Option<(u8, Vec<u8>, f32)>
+where
+    T: AsRef<[u8]>,
+    S: PlaintextScorer + ?Sized,
+{
     (0u8..=255)
         .map(|k| {
             let guess: Vec<u8> = data.as_ref().iter().map(|b| b ^ k).collect();
-            let rating = rate_english_frequency(&guess);
+            let rating = scorer.score(&guess);
             (guess, k, rating)
         })
         .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal))
         .map(|(vec, key, rating)| (key, vec.into_iter().collect(), rating))
 }
 
+/// Candidate of a single-byte XOR search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XorCandidate {
+    pub key: u8,
+    pub plaintext: Vec<u8>,
+    pub score: f32,
+    /// Score difference to the next candidate in the ranking, zero for the last one.
+    pub margin: f32,
+}
+
+/// Difference of scores which treats equal infinite scores as a zero margin.
+pub(crate) fn score_margin(score: f32, next: Option<f32>) -> f32 {
+    match next {
+        Some(next) if next != score => score - next,
+        _ => 0.0,
+    }
+}
+
+/// Sorts by descending score and truncates to `count`, returns margins of
+/// the remaining candidates computed before truncation.
+pub(crate) fn rank_by_score<C, F>(candidates: &mut Vec<C>, count: usize, score: F) -> Vec<f32>
+where
+    F: Fn(&C) -> f32,
+{
+    candidates.sort_by(|a, b| score(b).partial_cmp(&score(a)).unwrap_or(Ordering::Equal));
+    let margins = (0..candidates.len().min(count))
+        .map(|idx| score_margin(score(&candidates[idx]), candidates.get(idx + 1).map(&score)))
+        .collect();
+    candidates.truncate(count);
+    margins
+}
+
+/// Returns at most `count` best candidates, best first.
+pub fn rank_single_byte_xor<T: AsRef<[u8]>>(data: T, count: usize) -> Vec<XorCandidate> {
+    rank_single_byte_xor_with(data, count, &FrequencySum)
+}
+
+pub fn rank_single_byte_xor_with<T, S>(data: T, count: usize, scorer: &S) -> Vec<XorCandidate>
+where
+    T: AsRef<[u8]>,
+    S: PlaintextScorer + ?Sized,
+{
+    let mut candidates = (0u8..=255)
+        .map(|key| {
+            let plaintext: Vec<u8> = data.as_ref().iter().map(|b| b ^ key).collect();
+            let score = scorer.score(&plaintext);
+            XorCandidate {
+                key,
+                plaintext,
+                score,
+                margin: 0.0,
+            }
+        })
+        .collect::<Vec<_>>();
+    let margins = rank_by_score(&mut candidates, count, |c| c.score);
+    for (candidate, margin) in candidates.iter_mut().zip(margins) {
+        candidate.margin = margin;
+    }
+    candidates
+}
+
 pub fn brute_single_byte_xor_str(hex: &str) -> Option<(u8, String, f32)> {
     let bytes = hex::decode(hex).ok()?;
     brute_single_byte_xor(&bytes)
@@ -79,8 +156,31 @@ mod tests {
             Some((
                 88u8,
                 "Cooking MC\'s like a pound of bacon".to_owned(),
-                2.2462904
+                2.200425
             ))
         );
+        let english = LanguageModel::english();
+        let guess = b"Cooking MC's like a pound of bacon";
+        assert!((rate_frequency(guess, &english) - rate_english_frequency(guess)).abs() < 0.1);
+    }
+
+    #[test]
+    fn rank_single_byte_xor_should_pass() {
+        let cipher =
+            hex::decode("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736")
+                .unwrap();
+        let ranked = rank_single_byte_xor(&cipher, 3);
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].key, 88);
+        assert_eq!(ranked[0].plaintext, b"Cooking MC's like a pound of bacon");
+        assert_eq!(ranked[0].margin, ranked[0].score - ranked[1].score);
+        assert!(ranked[0].margin > 0.0);
+        assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score));
+        // last candidate still gets a margin to the dropped runner-up
+        let all = rank_single_byte_xor(&cipher, 300);
+        assert_eq!(all.len(), 256);
+        assert_eq!(all[..3], ranked[..]);
+        assert_eq!(all[255].margin, 0.0);
+        assert!(rank_single_byte_xor(&cipher, 0).is_empty());
     }
 }
@@ -1,39 +1,454 @@
 /// Task: Find single-byte XOR
-use crate::prelude::brute_single_byte_xor_str;
-use std::cmp::Ordering;
+use crate::codec::{Codec, Hex};
+use crate::scoring::{byte_histogram, FrequencySum, HistogramScorer, PlaintextScorer};
+use crate::task_3::{
+    brute_single_byte_xor_with, rank_by_score, rank_single_byte_xor_with, XorCandidate,
+};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::Path;
+use std::sync::Arc;
 
 pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 where
     P: AsRef<Path>,
 {
-    let file = File::open(filename).unwrap();
+    let file = File::open(filename)?;
     Ok(io::BufReader::new(file).lines())
 }
 
 pub fn find_single_byte_xor<P>(path: P) -> Result<(u8, String, f32), String>
 where
     P: AsRef<Path>,
+{
+    let reader = File::open(path)
+        .map(io::BufReader::new)
+        .map_err(|_| "Failed to read file".to_owned())?;
+    let found = detect_single_byte_xor(reader, 1, &Hex::default(), &FrequencySum)
+        .map_err(|_| "Failed to read file".to_owned())?;
+    found
+        .into_iter()
+        .next()
+        .map(|LineCandidate { candidate, .. }| {
+            let guess = candidate.plaintext.into_iter().map(|b| b as char).collect();
+            (candidate.key, guess, candidate.score)
+        })
+        .ok_or_else(|| "Can't find best candidate".to_owned())
+}
+
+/// Same as `find_single_byte_xor`, but lines are decoded with the given codec,
+/// lines which fail to decode are skipped, candidates are rated by the given scorer.
+pub fn find_single_byte_xor_with<P, C, S>(
+    path: P,
+    codec: &C,
+    scorer: &S,
+) -> Result<(u8, String, f32), String>
+where
+    P: AsRef<Path>,
+    C: Codec,
+    S: PlaintextScorer + ?Sized,
 {
     let lines = read_lines(path).map_err(|_| "Failed to read file".to_owned())?;
+    // lines which aren't valid UTF-8 are skipped, the scan goes on after them
+    #[allow(clippy::lines_filter_map_ok)]
     lines
-        .filter_map(|lr| lr.ok())
-        .filter_map(|line| brute_single_byte_xor_str(&line))
+        .filter_map(Result::ok)
+        .filter_map(|line| codec.decode(&line).ok())
+        .filter_map(|bytes| brute_single_byte_xor_with(bytes, scorer))
+        .map(|(key, guess, rating)| (key, guess.into_iter().map(|b| b as char).collect(), rating))
         .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal))
         .ok_or_else(|| "Can't find best candidate".to_owned())
 }
 
+/// Candidate found in a file, `line` is counted from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineCandidate {
+    pub line: usize,
+    pub candidate: XorCandidate,
+}
+
+/// Returns at most `count` best candidates over all lines, best first.
+/// Margins are relative to the next candidate in this ranking, possibly from another line.
+pub fn rank_single_byte_xor_lines<P>(path: P, count: usize) -> Result<Vec<LineCandidate>, String>
+where
+    P: AsRef<Path>,
+{
+    let reader = File::open(path)
+        .map(io::BufReader::new)
+        .map_err(|_| "Failed to read file".to_owned())?;
+    detect_single_byte_xor(reader, count, &Hex::default(), &FrequencySum)
+        .map_err(|_| "Failed to read file".to_owned())
+}
+
+pub fn rank_single_byte_xor_lines_with<P, C, S>(
+    path: P,
+    count: usize,
+    codec: &C,
+    scorer: &S,
+) -> Result<Vec<LineCandidate>, String>
+where
+    P: AsRef<Path>,
+    C: Codec,
+    S: PlaintextScorer + ?Sized,
+{
+    let lines = read_lines(path).map_err(|_| "Failed to read file".to_owned())?;
+    let mut candidates = Vec::new();
+    // enumerated before skipping, so line numbers match the file
+    for (idx, line) in lines
+        .enumerate()
+        .filter_map(|(idx, line)| line.ok().map(|line| (idx, line)))
+    {
+        let bytes = match codec.decode(&line) {
+            Ok(bytes) => bytes,
+            Err(_) => continue,
+        };
+        // one extra candidate per line keeps margin of the last kept one exact
+        candidates.extend(
+            rank_single_byte_xor_with(bytes, count + 1, scorer)
+                .into_iter()
+                .map(|candidate| LineCandidate {
+                    line: idx + 1,
+                    candidate,
+                }),
+        );
+    }
+    let margins = rank_by_score(&mut candidates, count, |c| c.candidate.score);
+    for (found, margin) in candidates.iter_mut().zip(margins) {
+        found.candidate.margin = margin;
+    }
+    Ok(candidates)
+}
+
+/// Keeps `capacity` greatest of the pushed items.
+#[derive(Clone, Debug)]
+pub struct TopK<T: Ord> {
+    capacity: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity + 1),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.heap.len() < self.capacity {
+            self.heap.push(Reverse(item));
+        } else if self.heap.peek().is_some_and(|min| item > min.0) {
+            self.heap.pop();
+            self.heap.push(Reverse(item));
+        }
+    }
+
+    pub fn merge(mut self, other: Self) -> Self {
+        for Reverse(item) in other.heap {
+            self.push(item);
+        }
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Greatest item first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect()
+    }
+}
+
+/// Key candidate of a line, ties are resolved in favour of later lines and greater keys.
+#[derive(Clone, Debug)]
+struct RankedKey {
+    score: f32,
+    line: usize,
+    key: u8,
+    cipher: Arc<Vec<u8>>,
+}
+
+impl Ord for RankedKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then(self.line.cmp(&other.line))
+            .then(self.key.cmp(&other.key))
+    }
+}
+
+impl PartialOrd for RankedKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for RankedKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RankedKey {}
+
+fn rate_line<C, S>(top: &mut TopK<RankedKey>, idx: usize, line: &str, codec: &C, scorer: &S)
+where
+    C: Codec,
+    S: HistogramScorer + ?Sized,
+{
+    let cipher = match codec.decode(line) {
+        Ok(cipher) => Arc::new(cipher),
+        Err(_) => return,
+    };
+    let histogram = byte_histogram(cipher.as_slice());
+    for key in 0u8..=255 {
+        top.push(RankedKey {
+            score: scorer.score_histogram(&histogram, key),
+            line: idx + 1,
+            key,
+            cipher: Arc::clone(&cipher),
+        });
+    }
+}
+
+/// Lines which aren't valid UTF-8 are skipped like undecodable ones,
+/// other read errors are passed on.
+fn text_line(line: io::Result<String>) -> io::Result<Option<String>> {
+    match line {
+        Ok(line) => Ok(Some(line)),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn scan_lines<R, C, S>(
+    reader: R,
+    capacity: usize,
+    codec: &C,
+    scorer: &S,
+) -> io::Result<TopK<RankedKey>>
+where
+    R: BufRead + Send,
+    C: Codec + Sync,
+    S: HistogramScorer + Sync + ?Sized,
+{
+    use rayon::prelude::*;
+    reader
+        .lines()
+        .enumerate()
+        .par_bridge()
+        .try_fold(
+            || TopK::new(capacity),
+            |mut top, (idx, line)| {
+                if let Some(line) = text_line(line)? {
+                    rate_line(&mut top, idx, &line, codec, scorer);
+                }
+                Ok(top)
+            },
+        )
+        .try_reduce(|| TopK::new(capacity), |a, b| Ok(a.merge(b)))
+}
+
+#[cfg(not(feature = "parallel"))]
+fn scan_lines<R, C, S>(
+    reader: R,
+    capacity: usize,
+    codec: &C,
+    scorer: &S,
+) -> io::Result<TopK<RankedKey>>
+where
+    R: BufRead + Send,
+    C: Codec + Sync,
+    S: HistogramScorer + Sync + ?Sized,
+{
+    let mut top = TopK::new(capacity);
+    for (idx, line) in reader.lines().enumerate() {
+        if let Some(line) = text_line(line)? {
+            rate_line(&mut top, idx, &line, codec, scorer);
+        }
+    }
+    Ok(top)
+}
+
+/// Streaming search of lines encrypted with single-byte XOR, returns at most `count`
+/// best candidates, best first. Lines which fail to decode are skipped.
+///
+/// Keys are rated by byte histograms of lines, so plaintexts are only produced for
+/// the kept candidates, which are then rescored with [`PlaintextScorer::score`].
+/// With the `parallel` feature lines are rated on the rayon thread pool.
+pub fn detect_single_byte_xor<R, C, S>(
+    reader: R,
+    count: usize,
+    codec: &C,
+    scorer: &S,
+) -> io::Result<Vec<LineCandidate>>
+where
+    R: BufRead + Send,
+    C: Codec + Sync,
+    S: HistogramScorer + Sync + ?Sized,
+{
+    // extra candidate keeps margin of the last kept one
+    let top = scan_lines(reader, count + 1, codec, scorer)?;
+    let mut candidates = top
+        .into_sorted_vec()
+        .into_iter()
+        .map(|ranked| {
+            let plaintext = ranked
+                .cipher
+                .iter()
+                .map(|b| b ^ ranked.key)
+                .collect::<Vec<_>>();
+            LineCandidate {
+                line: ranked.line,
+                candidate: XorCandidate {
+                    key: ranked.key,
+                    score: scorer.score(&plaintext),
+                    plaintext,
+                    margin: 0.0,
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+    let margins = rank_by_score(&mut candidates, count, |c| c.candidate.score);
+    for (found, margin) in candidates.iter_mut().zip(margins) {
+        found.candidate.margin = margin;
+    }
+    Ok(candidates)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::scoring::{ChiSquared, Printable};
+    use crate::task_1::Base64Config;
 
     #[test]
     fn find_single_byte_xor_should_pass() {
         assert_eq!(
             find_single_byte_xor("res/task4.txt"),
-            Ok((53u8, "Now that the party is jumping\n".to_owned(), 2.123412))
+            Ok((
+                53u8,
+                "Now that the party is jumping\n".to_owned(),
+                2.0316803
+            ))
+        );
+    }
+
+    #[test]
+    fn find_single_byte_xor_with_codec_should_pass() {
+        let path =
+            std::env::temp_dir().join(format!("set_1_task_4_base64_{}.txt", std::process::id()));
+        let lines = read_lines("res/task4.txt")
+            .unwrap()
+            .map(|line| {
+                let bytes = Hex::default().decode(&line.unwrap()).unwrap();
+                Base64Config::STANDARD.encode(bytes)
+            })
+            .collect::<Vec<_>>();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let found = find_single_byte_xor_with(&path, &Base64Config::STANDARD, &FrequencySum);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            found,
+            Ok((
+                53u8,
+                "Now that the party is jumping\n".to_owned(),
+                2.0316803
+            ))
+        );
+    }
+
+    #[test]
+    fn find_single_byte_xor_invalid_utf8_should_pass() {
+        let path = std::env::temp_dir().join(format!(
+            "set_1_task_4_invalid_utf8_{}.txt",
+            std::process::id()
+        ));
+        let mut content = b"\xff\xfe\n".to_vec();
+        content.extend(std::fs::read("res/task4.txt").unwrap());
+        std::fs::write(&path, content).unwrap();
+        let found = find_single_byte_xor_with(&path, &Hex::default(), &FrequencySum);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(found, find_single_byte_xor("res/task4.txt"));
+    }
+
+    #[test]
+    fn rank_single_byte_xor_lines_should_pass() {
+        let ranked = rank_single_byte_xor_lines("res/task4.txt", 5).unwrap();
+        assert_eq!(ranked.len(), 5);
+        assert_eq!(ranked[0].line, 171);
+        assert_eq!(ranked[0].candidate.key, 53);
+        assert_eq!(
+            ranked[0].candidate.plaintext,
+            b"Now that the party is jumping\n"
+        );
+        assert_eq!(
+            ranked[0].candidate.margin,
+            ranked[0].candidate.score - ranked[1].candidate.score
+        );
+        assert!(ranked[0].candidate.margin > 0.0);
+        assert!(rank_single_byte_xor_lines("res/missing.txt", 5).is_err());
+
+        let path = std::env::temp_dir().join(format!(
+            "set_1_task_4_rank_invalid_utf8_{}.txt",
+            std::process::id()
+        ));
+        let mut content = b"\xff\xfe\n".to_vec();
+        content.extend(std::fs::read("res/task4.txt").unwrap());
+        std::fs::write(&path, content).unwrap();
+        let shifted = rank_single_byte_xor_lines_with(&path, 5, &Hex::default(), &FrequencySum);
+        std::fs::remove_file(&path).unwrap();
+        let shifted = shifted.unwrap();
+        assert_eq!(shifted.len(), 5);
+        assert_eq!((shifted[0].line, shifted[0].candidate.key), (172, 53));
+    }
+
+    #[test]
+    fn top_k_should_pass() {
+        let mut top = TopK::new(3);
+        for item in [5, 1, 9, 3, 7, 2] {
+            top.push(item);
+        }
+        assert_eq!(top.len(), 3);
+        let mut other = TopK::new(3);
+        other.push(8);
+        assert_eq!(top.merge(other).into_sorted_vec(), vec![9, 8, 7]);
+        let mut empty = TopK::new(0);
+        empty.push(1);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn detect_single_byte_xor_should_pass() {
+        let reader = io::BufReader::new(File::open("res/task4.txt").unwrap());
+        let detected = detect_single_byte_xor(reader, 5, &Hex::default(), &FrequencySum).unwrap();
+        assert_eq!(
+            detected,
+            rank_single_byte_xor_lines_with("res/task4.txt", 5, &Hex::default(), &FrequencySum)
+                .unwrap()
         );
+
+        let scorer = (Printable::Ascii, ChiSquared::english());
+        let reader = io::BufReader::new(File::open("res/task4.txt").unwrap());
+        let detected = detect_single_byte_xor(reader, 1, &Hex::default(), &scorer).unwrap();
+        assert_eq!((detected[0].line, detected[0].candidate.key), (171, 53));
+
+        let mut content = b"\xff\xfe\n".to_vec();
+        content.extend(std::fs::read("res/task4.txt").unwrap());
+        let detected =
+            detect_single_byte_xor(&content[..], 1, &Hex::default(), &FrequencySum).unwrap();
+        assert_eq!((detected[0].line, detected[0].candidate.key), (172, 53));
     }
 }
@@ -1,5 +1,4 @@
 /// Task: Repeating-key XOR
-
 pub fn repeating_key_xor<T: AsRef<[u8]>>(cipher: T, key: T) -> Vec<u8> {
     let key_ref = key.as_ref();
     cipher
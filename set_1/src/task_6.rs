@@ -1,25 +1,12 @@
 /// Task: Break repeating-key XOR
-use crate::task_3::{brute_single_byte_xor, rate_english_frequency};
+use crate::distance::{Distance, Hamming};
+use crate::scoring::{FrequencySum, PlaintextScorer};
+use crate::task_3::rank_single_byte_xor_with;
 use crate::task_5::repeating_key_xor;
+use std::collections::HashMap;
 
 pub fn hamming_distance<T: AsRef<[u8]>>(slice_a: T, slice_b: T) -> Option<u32> {
-    let a_ref = slice_a.as_ref();
-    let b_ref = slice_b.as_ref();
-    if a_ref.len() != b_ref.len() {
-        return None;
-    }
-    Some(
-        a_ref
-            .iter()
-            .zip(b_ref.iter())
-            .map(|(&a, &b)| a ^ b)
-            .map(|v| {
-                (0..8)
-                    .map(|idx| (((1u8 << idx) & v) >> idx) as u32)
-                    .sum::<u32>()
-            })
-            .sum(),
-    )
+    Hamming::EXACT.bits(slice_a.as_ref(), slice_b.as_ref())
 }
 
 pub fn string_distance(str_a: &str, str_b: &str) -> Option<u32> {
@@ -27,45 +14,244 @@ pub fn string_distance(str_a: &str, str_b: &str) -> Option<u32> {
 }
 
 pub fn normalized_edit_distance<T: AsRef<[u8]>>(data_a: T, data_b: T) -> Option<f64> {
-    let len = data_a.as_ref().len();
-    hamming_distance(data_a, data_b).map(|dist| dist as f64 / len as f64)
+    Hamming::EXACT.normalized(data_a.as_ref(), data_b.as_ref())
+}
+
+/// Upper bound of blocks compared pairwise by [`KeysizeStrategy::Hamming`].
+const MAX_HAMMING_BLOCKS: usize = 128;
+/// Length of n-grams searched by [`KeysizeStrategy::Kasiski`].
+const KASISKI_NGRAM_LEN: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeysizeStrategy {
+    /// Average normalized distance over all pairs of keysize-long blocks,
+    /// at most `MAX_HAMMING_BLOCKS` leading blocks are used. Blocks are compared
+    /// by Hamming distance unless another one is given to `estimate_with`.
+    Hamming,
+    /// Average index of coincidence of columns encrypted by the same key byte.
+    IndexOfCoincidence,
+    /// Share of spacings between repeated trigrams which are multiples of keysize,
+    /// minus the share expected by chance.
+    Kasiski,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeysizeError {
+    /// Range is empty, starts from zero or holds fewer keysizes than requested.
+    InvalidRange { min: usize, max: usize },
+    /// Cipher is shorter than `required` bytes needed to rate the smallest keysize.
+    CipherTooShort { len: usize, required: usize },
+    /// Kasiski examination found no repeated n-grams.
+    NoRepeats,
+}
+
+impl std::fmt::Display for KeysizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeysizeError::InvalidRange { min, max } => {
+                write!(f, "invalid keysize range {}..={}", min, max)
+            }
+            KeysizeError::CipherTooShort { len, required } => write!(
+                f,
+                "cipher of {} bytes is too short, at least {} required",
+                len, required
+            ),
+            KeysizeError::NoRepeats => write!(f, "no repeated n-grams in cipher"),
+        }
+    }
+}
+
+impl std::error::Error for KeysizeError {}
+
+/// Keysize with its score, higher is better.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeysizeCandidate {
+    pub keysize: usize,
+    pub score: f64,
+}
+
+/// Rates keysizes in `min..=max`, keysizes too large to be rated on the given
+/// cipher are skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeysizeEstimator {
+    pub strategy: KeysizeStrategy,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl Default for KeysizeEstimator {
+    fn default() -> Self {
+        Self {
+            strategy: KeysizeStrategy::Hamming,
+            min: 2,
+            max: 40,
+        }
+    }
+}
+
+impl KeysizeEstimator {
+    /// Returns rated keysizes, best first, equal scores are ordered by keysize.
+    pub fn estimate<T: AsRef<[u8]>>(
+        &self,
+        cipher: T,
+    ) -> Result<Vec<KeysizeCandidate>, KeysizeError> {
+        self.estimate_with(cipher, &Hamming::EXACT)
+    }
+
+    /// Same as `estimate`, blocks are compared by the given distance.
+    pub fn estimate_with<T, D>(
+        &self,
+        cipher: T,
+        distance: &D,
+    ) -> Result<Vec<KeysizeCandidate>, KeysizeError>
+    where
+        T: AsRef<[u8]>,
+        D: Distance + ?Sized,
+    {
+        let cipher_ref = cipher.as_ref();
+        if self.min == 0 || self.min > self.max {
+            return Err(KeysizeError::InvalidRange {
+                min: self.min,
+                max: self.max,
+            });
+        }
+        // every strategy needs at least two bytes under each key byte
+        let required = 2 * self.min;
+        if cipher_ref.len() < required {
+            return Err(KeysizeError::CipherTooShort {
+                len: cipher_ref.len(),
+                required,
+            });
+        }
+        let keysizes = self.min..=self.max.min(cipher_ref.len() / 2);
+        let mut candidates = match self.strategy {
+            KeysizeStrategy::Hamming => keysizes
+                .map(|keysize| KeysizeCandidate {
+                    keysize,
+                    score: -average_block_distance(cipher_ref, keysize, distance),
+                })
+                .collect::<Vec<_>>(),
+            KeysizeStrategy::IndexOfCoincidence => keysizes
+                .map(|keysize| KeysizeCandidate {
+                    keysize,
+                    score: average_coincidence(cipher_ref, keysize),
+                })
+                .collect(),
+            KeysizeStrategy::Kasiski => {
+                let spacings = repeat_spacings(cipher_ref, KASISKI_NGRAM_LEN);
+                if spacings.is_empty() {
+                    return Err(KeysizeError::NoRepeats);
+                }
+                keysizes
+                    .map(|keysize| {
+                        let multiples = spacings.iter().filter(|&&d| d % keysize == 0).count();
+                        KeysizeCandidate {
+                            keysize,
+                            score: multiples as f64 / spacings.len() as f64 - 1.0 / keysize as f64,
+                        }
+                    })
+                    .collect()
+            }
+        };
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.keysize.cmp(&b.keysize)));
+        Ok(candidates)
+    }
 }
 
+fn average_block_distance<D>(cipher: &[u8], keysize: usize, distance: &D) -> f64
+where
+    D: Distance + ?Sized,
+{
+    let blocks = cipher
+        .chunks_exact(keysize)
+        .take(MAX_HAMMING_BLOCKS)
+        .collect::<Vec<_>>();
+    let mut total = 0.0;
+    let mut pairs = 0;
+    for (idx, a) in blocks.iter().enumerate() {
+        for b in blocks[idx + 1..].iter() {
+            total += distance
+                .normalized(a, b)
+                .expect("blocks are of equal length");
+            pairs += 1;
+        }
+    }
+    total / pairs as f64
+}
+
+fn average_coincidence(cipher: &[u8], keysize: usize) -> f64 {
+    let columns = split_into_transposed_chunks(cipher, keysize);
+    let rated = columns
+        .iter()
+        .filter(|column| column.len() > 1)
+        .map(|column| {
+            let mut counts = [0usize; 256];
+            column.iter().for_each(|&b| counts[b as usize] += 1);
+            let len = column.len();
+            let coincidences = counts
+                .iter()
+                .map(|&n| n * n.saturating_sub(1))
+                .sum::<usize>();
+            coincidences as f64 / (len * (len - 1)) as f64
+        })
+        .collect::<Vec<_>>();
+    rated.iter().sum::<f64>() / rated.len() as f64
+}
+
+/// Distances between consecutive occurrences of every repeated n-gram.
+fn repeat_spacings(cipher: &[u8], n: usize) -> Vec<usize> {
+    let mut last_seen = HashMap::<&[u8], usize>::new();
+    let mut spacings = Vec::new();
+    for (pos, gram) in cipher.windows(n).enumerate() {
+        if let Some(prev) = last_seen.insert(gram, pos) {
+            spacings.push(pos - prev);
+        }
+    }
+    spacings
+}
+
+/// Returns `best_count` keysizes in `keymin..keymax` rated by [`KeysizeStrategy::Hamming`].
 pub fn find_keysizes<T: AsRef<[u8]>>(
     cipher: T,
     best_count: Option<u8>,
     keymin: Option<u8>,
     keymax: Option<u8>,
-) -> Option<Vec<u8>> {
-    let cipher_ref = cipher.as_ref();
+) -> Result<Vec<u8>, KeysizeError> {
+    find_keysizes_with(cipher, best_count, keymin, keymax, &Hamming::EXACT)
+}
+
+/// Same as `find_keysizes`, blocks are compared by the given distance.
+pub fn find_keysizes_with<T, D>(
+    cipher: T,
+    best_count: Option<u8>,
+    keymin: Option<u8>,
+    keymax: Option<u8>,
+    distance: &D,
+) -> Result<Vec<u8>, KeysizeError>
+where
+    T: AsRef<[u8]>,
+    D: Distance + ?Sized,
+{
     let best_count = best_count.unwrap_or(3);
     let keymin = keymin.unwrap_or(2);
     let keymax = keymax.unwrap_or(40);
-    if best_count > keymax - keymin {
-        return None;
+    if keymin == 0 || keymax <= keymin || best_count > keymax - keymin {
+        return Err(KeysizeError::InvalidRange {
+            min: keymin as usize,
+            max: keymax.saturating_sub(1) as usize,
+        });
     }
-    let mut distances = Vec::new();
-    for keysize in keymin..keymax {
-        let chunks_num = cipher_ref.len() / keysize as usize;
-        let mut avg_distance: f64 = 0.0;
-        for cn in 0..chunks_num - 1 {
-            let ks = keysize as usize;
-            avg_distance += normalized_edit_distance(
-                &cipher_ref[cn..cn + ks],
-                &cipher_ref[cn + ks..cn + 2 * ks],
-            )?;
-        }
-        avg_distance /= chunks_num as f64 - 1.0;
-        distances.push((keysize, avg_distance));
-    }
-    distances.sort_by(|(_a_ks, a_ed), (_b_ks, b_ed)| a_ed.partial_cmp(b_ed).unwrap());
-    Some(
-        distances
-            .iter()
-            .take(best_count as usize)
-            .map(|(ks, _ed)| *ks)
-            .collect(),
-    )
+    let estimator = KeysizeEstimator {
+        strategy: KeysizeStrategy::Hamming,
+        min: keymin as usize,
+        max: keymax as usize - 1,
+    };
+    Ok(estimator
+        .estimate_with(cipher, distance)?
+        .into_iter()
+        .take(best_count as usize)
+        .map(|candidate| candidate.keysize as u8)
+        .collect())
 }
 
 pub fn split_into_transposed_chunks<T: AsRef<[u8]>>(data: T, chunk_size: usize) -> Vec<Vec<u8>> {
@@ -83,35 +269,160 @@ pub fn split_into_transposed_chunks<T: AsRef<[u8]>>(data: T, chunk_size: usize)
     result
 }
 
-/// returns best key
-pub fn brute_repeating_key_xor<T: AsRef<[u8]>>(cipher: T) -> Option<Vec<u8>> {
-    let cipher_ref = cipher.as_ref();
-    let keysizes = find_keysizes(&cipher, None, None, None)?;
-    let mut max_rate: f32 = 0.0;
-    let mut best_key = Vec::new();
-    for &ks in keysizes.iter() {
-        let mut guess_key = Vec::new();
-        for chunk in split_into_transposed_chunks(&cipher, ks as usize).iter() {
-            guess_key.push(brute_single_byte_xor(chunk).map(|(k, ..)| k)?)
+/// Number of best keysizes which are tried by `brute_repeating_key_xor`.
+const KEYSIZE_ATTEMPTS: usize = 3;
+
+/// Best byte of a key column and its runner-up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyColumn {
+    pub key: u8,
+    pub score: f32,
+    pub runner_up: u8,
+    /// Score difference to the runner-up, confidence of the column.
+    pub margin: f32,
+}
+
+/// Best key of the given size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeysizeAttempt {
+    pub keysize: usize,
+    /// Score given by the keysize estimator.
+    pub estimate: f64,
+    pub key: Vec<u8>,
+    /// Score of the whole plaintext decrypted with `key`.
+    pub score: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakReport {
+    pub key: Vec<u8>,
+    pub plaintext: Vec<u8>,
+    pub score: f32,
+    /// All tried keysizes, best first, scores are taken before refinement.
+    pub attempts: Vec<KeysizeAttempt>,
+    /// Columns of the chosen key.
+    pub columns: Vec<KeyColumn>,
+    /// Columns in which refinement replaced the byte with its runner-up.
+    pub refined: Vec<usize>,
+}
+
+fn rate_columns<S>(cipher: &[u8], keysize: usize, scorer: &S) -> Vec<KeyColumn>
+where
+    S: PlaintextScorer + ?Sized,
+{
+    split_into_transposed_chunks(cipher, keysize)
+        .iter()
+        .map(|column| {
+            let ranked = rank_single_byte_xor_with(column, 2, scorer);
+            KeyColumn {
+                key: ranked[0].key,
+                score: ranked[0].score,
+                runner_up: ranked[1].key,
+                margin: ranked[0].margin,
+            }
+        })
+        .collect()
+}
+
+/// Tries runner-up bytes in columns with margin below half of the average one,
+/// least confident first. Change is kept when it improves score of the whole plaintext.
+/// Returns indices of changed columns in ascending order.
+pub fn refine_repeating_key<S>(
+    cipher: &[u8],
+    key: &mut [u8],
+    columns: &[KeyColumn],
+    scorer: &S,
+) -> Vec<usize>
+where
+    S: PlaintextScorer + ?Sized,
+{
+    let finite = columns
+        .iter()
+        .map(|column| column.margin)
+        .filter(|margin| margin.is_finite())
+        .collect::<Vec<_>>();
+    if finite.is_empty() {
+        return Vec::new();
+    }
+    let threshold = finite.iter().sum::<f32>() / finite.len() as f32 / 2.0;
+    let mut uncertain = (0..columns.len().min(key.len()))
+        .filter(|&idx| columns[idx].margin < threshold)
+        .collect::<Vec<_>>();
+    uncertain.sort_by(|&a, &b| columns[a].margin.total_cmp(&columns[b].margin));
+
+    let mut best_score = scorer.score(&repeating_key_xor(cipher, &*key));
+    let mut refined = Vec::new();
+    for idx in uncertain {
+        let original = key[idx];
+        key[idx] = columns[idx].runner_up;
+        let score = scorer.score(&repeating_key_xor(cipher, &*key));
+        if score > best_score {
+            best_score = score;
+            refined.push(idx);
+        } else {
+            key[idx] = original;
         }
-        let guess = repeating_key_xor(cipher_ref, guess_key.as_ref());
-        let rate = rate_english_frequency(&guess);
-        if rate > max_rate {
-            max_rate = rate;
-            best_key = guess_key;
+    }
+    refined.sort_unstable();
+    refined
+}
+
+pub fn brute_repeating_key_xor<T: AsRef<[u8]>>(cipher: T) -> Result<BreakReport, KeysizeError> {
+    brute_repeating_key_xor_with(cipher, &FrequencySum)
+}
+
+/// Same as `brute_repeating_key_xor`, but columns and whole guesses are rated by the given scorer.
+pub fn brute_repeating_key_xor_with<T, S>(
+    cipher: T,
+    scorer: &S,
+) -> Result<BreakReport, KeysizeError>
+where
+    T: AsRef<[u8]>,
+    S: PlaintextScorer + ?Sized,
+{
+    let cipher_ref = cipher.as_ref();
+    let keysizes = KeysizeEstimator::default().estimate(cipher_ref)?;
+    let mut best: Option<(KeysizeAttempt, Vec<KeyColumn>)> = None;
+    let mut attempts = Vec::new();
+    for candidate in keysizes.into_iter().take(KEYSIZE_ATTEMPTS) {
+        let columns = rate_columns(cipher_ref, candidate.keysize, scorer);
+        let key = columns.iter().map(|column| column.key).collect::<Vec<_>>();
+        let attempt = KeysizeAttempt {
+            keysize: candidate.keysize,
+            estimate: candidate.score,
+            score: scorer.score(&repeating_key_xor(cipher_ref, &key)),
+            key,
+        };
+        if best.as_ref().is_none_or(|(b, _)| attempt.score > b.score) {
+            best = Some((attempt.clone(), columns));
         }
+        attempts.push(attempt);
     }
-    Some(best_key)
+    let (chosen, columns) = best.expect("estimator returns at least one keysize");
+    attempts.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let mut key = chosen.key;
+    let refined = refine_repeating_key(cipher_ref, &mut key, &columns, scorer);
+    let plaintext = repeating_key_xor(cipher_ref, &key);
+    Ok(BreakReport {
+        score: scorer.score(&plaintext),
+        key,
+        plaintext,
+        attempts,
+        columns,
+        refined,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::task_1::base64_to_bytes;
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use crate::input::load_base64_file;
+    use crate::scoring::{ChiSquared, Printable};
+    use crate::task_3::rate_english_frequency;
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn break_repeating_key_xor_should_pass() {
         assert_eq!(
             string_distance("this is a test", "wokka wokka!!!"),
@@ -136,15 +447,10 @@ mod tests {
             vec![vec![1, 2, 3]]
         );
 
-        let file = File::open("res/task6.txt").expect("Failed to open file.");
-        let mut file_content = String::new();
-        BufReader::new(file)
-            .lines()
-            .for_each(|line| file_content.push_str(line.unwrap_or(String::from("")).as_str()));
-
-        let cipher = base64_to_bytes(&file_content).expect("Failed to decode base64.");
+        let cipher = load_base64_file("res/task6.txt").expect("Failed to load base64 file.");
 
-        let best_key = brute_repeating_key_xor(&cipher).expect("Failed to guess a key.");
+        let report = brute_repeating_key_xor(&cipher).expect("Failed to guess a key.");
+        let best_key = report.key.clone();
 
         let guess = repeating_key_xor(&cipher, &best_key)
             .iter()
@@ -153,5 +459,137 @@ mod tests {
 
         let answer = "I'm back and I'm ringin' the bell \nA rockin' on the mike while the fly girls yell \nIn ecstasy in the back of me \nWell that's my DJ Deshay cuttin' all them Z's \nHittin' hard and the girlies goin' crazy \nVanilla's on the mike, man I'm not lazy. \n\nI'm lettin' my drug kick in \nIt controls my mouth and I begin \nTo just let it flow, let my concepts go \nMy posse's to the side yellin', Go Vanilla Go! \n\nSmooth 'cause that's the way I will be \nAnd if you don't give a damn, then \nWhy you starin' at me \nSo get off 'cause I control the stage \nThere's no dissin' allowed \nI'm in my own phase \nThe girlies sa y they love me and that is ok \nAnd I can dance better than any kid n' play \n\nStage 2 -- Yea the one ya' wanna listen to \nIt's off my head so let the beat play through \nSo I can funk it up and make it sound good \n1-2-3 Yo -- Knock on some wood \nFor good luck, I like my rhymes atrocious \nSupercalafragilisticexpialidocious \nI'm an effect and that you can bet \nI can take a fly girl and make her wet. \n\nI'm like Samson -- Samson to Delilah \nThere's no denyin', You can try to hang \nBut you'll keep tryin' to get my style \nOver and over, practice makes perfect \nBut not if you're a loafer. \n\nYou'll get nowhere, no place, no time, no girls \nSoon -- Oh my God, homebody, you probably eat \nSpaghetti with a spoon! Come on and say it! \n\nVIP. Vanilla Ice yep, yep, I'm comin' hard like a rhino \nIntoxicating so you stagger like a wino \nSo punks stop trying and girl stop cryin' \nVanilla Ice is sellin' and you people are buyin' \n'Cause why the freaks are jockin' like Crazy Glue \nMovin' and groovin' trying to sing along \nAll through the ghetto groovin' this here song \nNow you're amazed by the VIP posse. \n\nSteppin' so hard like a German Nazi \nStartled by the bases hittin' ground \nThere's no trippin' on mine, I'm just gettin' down \nSparkamatic, I'm hangin' tight like a fanatic \nYou trapped me once and I thought that \nYou might have it \nSo step down and lend me your ear \n'89 in my time! You, '90 is my year. \n\nYou're weakenin' fast, YO! and I can tell it \nYour body's gettin' hot, so, so I can smell it \nSo don't be mad and don't be sad \n'Cause the lyrics belong to ICE, You can call me Dad \nYou're pitchin' a fit, so step back and endure \nLet the witch doctor, Ice, do the dance to cure \nSo come up close and don't be square \nYou wanna battle me -- Anytime, anywhere \n\nYou thought that I was weak, Boy, you're dead wrong \nSo come on, everybody and sing this song \n\nSay -- Play that funky music Say, go white boy, go white boy go \nplay that funky music Go white boy, go white boy, go \nLay down and boogie and play that funky music till you die. \n\nPlay that funky music Come on, Come on, let me hear \nPlay that funky music white boy you say it, say it \nPlay that funky music A little louder now \nPlay that funky music, white boy Come on, Come on, Come on \nPlay that funky music \n";
         assert_eq!(guess, answer);
+
+        let scorer = (Printable::Ascii, ChiSquared::english());
+        assert_eq!(
+            brute_repeating_key_xor_with(&cipher, &scorer).map(|report| report.key),
+            Ok(best_key)
+        );
+    }
+
+    #[test]
+    fn break_report_should_pass() {
+        let cipher = load_base64_file("res/task6.txt").expect("Failed to load base64 file.");
+        let report = brute_repeating_key_xor(&cipher).unwrap();
+        assert_eq!(report.key, b"Terminator X: Bring the noise");
+        assert_eq!(report.plaintext, repeating_key_xor(&cipher, &report.key));
+        assert_eq!(report.score, rate_english_frequency(&report.plaintext));
+        assert_eq!(report.attempts.len(), 3);
+        assert_eq!(report.attempts[0].keysize, 29);
+        assert!(report.attempts.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(report.columns.len(), 29);
+        assert!(report.columns.iter().all(|column| column.margin > 0.0));
+        assert!(report.refined.is_empty());
+
+        assert_eq!(
+            brute_repeating_key_xor(b"abc"),
+            Err(KeysizeError::CipherTooShort {
+                len: 3,
+                required: 4
+            })
+        );
+    }
+
+    #[test]
+    fn refine_repeating_key_should_pass() {
+        let plaintext = b"Cooking MC's like a pound of bacon, burning 'em if you ain't quick";
+        let true_key = b"ICE";
+        let cipher = repeating_key_xor(&plaintext[..], &true_key[..]);
+        let column = |key: u8, runner_up: u8, margin: f32| KeyColumn {
+            key,
+            score: 1.0,
+            runner_up,
+            margin,
+        };
+        // middle column picked a case-flipped byte, correct one is the runner-up
+        let columns = [
+            column(b'I', b'i', 1.0),
+            column(b'C' ^ 0x20, b'C', 0.01),
+            column(b'E', b'e', 1.0),
+        ];
+        let mut key = columns.iter().map(|c| c.key).collect::<Vec<_>>();
+        let refined = refine_repeating_key(&cipher, &mut key, &columns, &FrequencySum);
+        assert_eq!(refined, vec![1]);
+        assert_eq!(key, true_key);
+        // confident columns are left alone even when runner-up is better
+        let columns = [column(b'I' ^ 0x20, b'I', 1.0); 3];
+        let mut key = vec![b'I' ^ 0x20; 3];
+        assert!(refine_repeating_key(&cipher, &mut key, &columns, &FrequencySum).is_empty());
+    }
+
+    #[test]
+    fn keysize_estimator_should_pass() {
+        let cipher = load_base64_file("res/task6.txt").expect("Failed to load base64 file.");
+        let plaintext = repeating_key_xor(&cipher[..], b"Terminator X: Bring the noise");
+        let long_key = (0..300u32)
+            .map(|i| (i * 7919 % 251) as u8)
+            .collect::<Vec<_>>();
+        let long_cipher = repeating_key_xor(&plaintext, &long_key);
+        for strategy in [
+            KeysizeStrategy::Hamming,
+            KeysizeStrategy::IndexOfCoincidence,
+            KeysizeStrategy::Kasiski,
+        ] {
+            let estimator = KeysizeEstimator {
+                strategy,
+                ..KeysizeEstimator::default()
+            };
+            let candidates = estimator.estimate(&cipher).unwrap();
+            assert_eq!(candidates.len(), 39);
+            assert_eq!(candidates[0].keysize, 29);
+            assert!(candidates[0].score > candidates[1].score);
+
+            let estimator = KeysizeEstimator {
+                strategy,
+                min: 2,
+                max: 600,
+            };
+            assert_eq!(estimator.estimate(&long_cipher).unwrap()[0].keysize, 300);
+        }
+
+        let estimator = KeysizeEstimator::default();
+        assert_eq!(
+            estimator.estimate(b"abc"),
+            Err(KeysizeError::CipherTooShort {
+                len: 3,
+                required: 4
+            })
+        );
+        assert_eq!(estimator.estimate(b"abcd").unwrap().len(), 1);
+        let estimator = KeysizeEstimator {
+            min: 0,
+            ..KeysizeEstimator::default()
+        };
+        assert_eq!(
+            estimator.estimate(&cipher),
+            Err(KeysizeError::InvalidRange { min: 0, max: 40 })
+        );
+        let estimator = KeysizeEstimator {
+            strategy: KeysizeStrategy::Kasiski,
+            ..KeysizeEstimator::default()
+        };
+        assert_eq!(
+            estimator.estimate(b"abcdefgh"),
+            Err(KeysizeError::NoRepeats)
+        );
+        assert_eq!(
+            find_keysizes(b"ab", None, None, None),
+            Err(KeysizeError::CipherTooShort {
+                len: 2,
+                required: 4
+            })
+        );
+        assert_eq!(
+            find_keysizes(&cipher, Some(0), Some(0), Some(0)),
+            Err(KeysizeError::InvalidRange { min: 0, max: 0 })
+        );
+        assert_eq!(
+            find_keysizes(&cipher, Some(5), Some(2), Some(6)),
+            Err(KeysizeError::InvalidRange { min: 2, max: 5 })
+        );
+        assert_eq!(
+            find_keysizes_with(&cipher, None, None, None, &Hamming::TRUNCATE),
+            find_keysizes(&cipher, None, None, None)
+        );
     }
 }
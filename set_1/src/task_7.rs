@@ -1,9 +1,14 @@
 /// Task: Decipher AES in ECB mode
+use crate::aes::{Aes, DecryptError};
+use crate::block_cipher::decrypt_ecb;
+use crate::padding::{unpad_pkcs7, PaddingError};
+#[cfg(feature = "openssl")]
 use openssl::symm::{Cipher, Crypter, Mode};
 use std::iter::FromIterator;
 
 pub const AES_BLOCK_SIZE: usize = 16;
 
+#[cfg(feature = "openssl")]
 pub fn aes_decrypt_single_block<T: AsRef<[u8]>>(cipher: T, key: T) -> Vec<u8> {
     let cipher_ref = cipher.as_ref();
     let key_ref = key.as_ref();
@@ -25,15 +30,33 @@ pub fn aes_decrypt_single_block<T: AsRef<[u8]>>(cipher: T, key: T) -> Vec<u8> {
     output
 }
 
-pub struct RepeatingKey(Vec<u8>, usize);
+#[cfg(not(feature = "openssl"))]
+pub fn aes_decrypt_single_block<T: AsRef<[u8]>>(cipher: T, key: T) -> Vec<u8> {
+    let cipher_ref = cipher.as_ref();
+    let key_ref = key.as_ref();
+    assert_eq!(
+        cipher_ref.len(),
+        AES_BLOCK_SIZE,
+        "Input must be of block size"
+    );
+    assert_eq!(key_ref.len(), AES_BLOCK_SIZE, "Key must be of block size");
+
+    let aes = crate::aes::Aes::new(key_ref).expect("key is of block size");
+    let mut block: crate::aes::Block = cipher_ref.try_into().expect("input is of block size");
+    aes.decrypt_block(&mut block);
+    block.to_vec()
+}
+
+/// Repeated key, `take` repeats it from its first byte up to `count` bytes.
+pub struct RepeatingKey(Vec<u8>);
 
 impl RepeatingKey {
     pub fn new<T: AsRef<[u8]>>(key: T) -> Self {
-        Self(Vec::from_iter(key.as_ref().iter().copied()), 0)
+        Self(Vec::from_iter(key.as_ref().iter().copied()))
     }
 
     pub fn take(&mut self, count: usize) -> Option<Vec<u8>> {
-        if self.0.len() > 0 {
+        if !self.0.is_empty() {
             let mut res = Vec::new();
             let mut index = 0;
             while res.len() < count {
@@ -47,44 +70,33 @@ impl RepeatingKey {
     }
 }
 
-pub fn decrypt_aes_ecb<T: AsRef<[u8]>>(cipher: T, key: T) -> Vec<u8> {
+/// Key length selects AES-128, AES-192 or AES-256, PKCS#7 padding is validated and removed.
+pub fn decrypt_aes_ecb<T: AsRef<[u8]>>(cipher: T, key: T) -> Result<Vec<u8>, DecryptError> {
+    let aes = Aes::new(key.as_ref())?;
     let cipher_ref = cipher.as_ref();
-    let key_ref = key.as_ref();
-    assert_eq!(cipher_ref.len() % AES_BLOCK_SIZE, 0);
-    assert!(key_ref.len() > 0);
-    let full_blocks = cipher_ref.len() / AES_BLOCK_SIZE;
-    let mut rep_key = RepeatingKey::new(key_ref);
-    let mut output = Vec::new();
-    for i in 0..full_blocks {
-        let block_ref = &cipher_ref[i * AES_BLOCK_SIZE..(i + 1) * AES_BLOCK_SIZE];
-        let key_part = rep_key.take(AES_BLOCK_SIZE).unwrap();
-        let res_block = aes_decrypt_single_block(block_ref, key_part.as_slice());
-        output.extend(res_block.into_iter());
+    if cipher_ref.is_empty() || cipher_ref.len() % AES_BLOCK_SIZE != 0 {
+        return Err(PaddingError::InvalidLength {
+            len: cipher_ref.len(),
+            block_size: AES_BLOCK_SIZE,
+        }
+        .into());
     }
-    let last = *output.last().unwrap() as usize;
-    output.truncate(output.len() - last);
-    output
+    let output = decrypt_ecb(&aes, cipher_ref);
+    Ok(unpad_pkcs7(&output, AES_BLOCK_SIZE)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::task_1::base64_to_bytes;
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use crate::input::load_base64_file;
 
     #[test]
     fn decrypt_aes_ecb_should_pass() {
         let key: &[u8] = "YELLOW SUBMARINE".as_bytes();
 
-        let file = File::open("res/task7.txt").expect("Failed to open file.");
-        let mut file_content = String::new();
-        BufReader::new(file)
-            .lines()
-            .for_each(|line| file_content.push_str(line.unwrap_or(String::from("")).as_str()));
-
-        let cipher = base64_to_bytes(&file_content).expect("Failed to decode base64.");
+        let cipher = load_base64_file("res/task7.txt").expect("Failed to load base64 file.");
         let cleartext = decrypt_aes_ecb(cipher.as_ref(), key)
+            .unwrap()
             .iter()
             .map(|&v| v as char)
             .collect::<String>();
@@ -20,22 +20,20 @@ pub fn find_aes_ecb_repeats<T: AsRef<[u8]>>(cipher: T) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use crate::input::load_hex_lines;
 
     #[test]
     fn find_aes_ecb_repeats_should_pass() {
-        let file = File::open("res/task8.txt").expect("Failed to open file.");
+        let records = load_hex_lines("res/task8.txt").expect("Failed to load hex file.");
 
         let mut likely_ecb_cipher = String::new();
         let mut max_repeats: u32 = 0;
 
-        for line in BufReader::new(file).lines() {
-            let line = line.unwrap();
-            let repeats = find_aes_ecb_repeats(hex::decode(&line).expect("Malformed hex input."));
+        for record in records.iter() {
+            let repeats = find_aes_ecb_repeats(record);
             if repeats > max_repeats {
                 max_repeats = repeats;
-                likely_ecb_cipher = line;
+                likely_ecb_cipher = hex::encode(record);
             }
         }
 
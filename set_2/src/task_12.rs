@@ -7,7 +7,7 @@ pub fn brute_last_byte_in_block(
     known_prefix: &[u8],
 ) -> Option<u8> {
     for b in 0..=255u8 {
-        let mut cleartext = known_prefix.iter().copied().collect::<Vec<_>>();
+        let mut cleartext = known_prefix.to_vec();
         cleartext.push(b);
         let test_block = encrypt_aes_ecb(cleartext.as_slice(), key);
         if cipher_block == test_block.as_slice() {
@@ -25,22 +25,14 @@ mod tests {
     const EXPECTED_TEXT: &str = "Rollin' in my 5.0\nWith my rag-top down so my hair can blow\nThe girlies on standby waving just to say hi\nDid you stop? No, I just drove by\n";
 
     use super::*;
-    use set_1::task_1::base64_to_bytes;
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use set_1::input::load_base64_file;
 
     #[test]
     fn decrypt_aes_cbc_should_pass() {
         let known_key = random_bytes(AES_BLOCK_SIZE);
 
-        let file = File::open("res/task12.txt").expect("Failed to open file.");
-        let mut file_content = String::new();
-        BufReader::new(file)
-            .lines()
-            .for_each(|line| file_content.push_str(line.unwrap_or(String::from("")).as_str()));
-
-        let known_text = base64_to_bytes(&file_content).expect("Failed to decode base64.");
-        let padded_cipher = pad_to_block_size(known_text.as_slice(), AES_BLOCK_SIZE);
+        let known_text = load_base64_file("res/task12.txt").expect("Failed to load base64 file.");
+        let padded_cipher = pad_to_block_size(known_text.as_slice(), AES_BLOCK_SIZE).unwrap();
         let cipher = encrypt_aes_ecb(padded_cipher.as_slice(), known_key.as_slice());
 
         let mut probe_block = vec![42u8; AES_BLOCK_SIZE - 1];
@@ -57,7 +49,8 @@ mod tests {
                     .copied()
                     .chain(known_text.iter().copied())
                     .collect::<Vec<_>>();
-                let padded_cipher = pad_to_block_size(prefixed_cipher.as_slice(), AES_BLOCK_SIZE);
+                let padded_cipher =
+                    pad_to_block_size(prefixed_cipher.as_slice(), AES_BLOCK_SIZE).unwrap();
                 let cipher = encrypt_aes_ecb(padded_cipher.as_slice(), known_key.as_slice());
                 let cipher_ref = cipher.as_slice();
                 let byte = brute_last_byte_in_block(
@@ -1,10 +1,11 @@
 // Task: ECB cut-and-paste
 use crate::prelude::{encrypt_aes_ecb, pad_to_block_size};
+use set_1::aes::DecryptError;
 use set_1::task_7::{decrypt_aes_ecb, AES_BLOCK_SIZE};
 use std::collections::HashMap;
 
-pub fn encode_to_kv_sequence(data: &Vec<(String, String)>) -> String {
-    let sanitize = |s: &str| -> String { s.replace('&', "").replace('=', "") };
+pub fn encode_to_kv_sequence(data: &[(String, String)]) -> String {
+    let sanitize = |s: &str| -> String { s.replace(['&', '='], "") };
     let strs: Vec<_> = data
         .iter()
         .map(|(k, v)| format!("{}={}", sanitize(k), sanitize(v)))
@@ -16,11 +17,8 @@ pub fn parse_kv_sequence(input: &str) -> HashMap<String, String> {
     let mut result = HashMap::new();
     for pair in input.split('&') {
         let mut kv = pair.split('=');
-        match (kv.next(), kv.next()) {
-            (Some(key), Some(val)) => {
-                result.insert(key.to_string(), val.to_string());
-            }
-            _ => (),
+        if let (Some(key), Some(val)) = (kv.next(), kv.next()) {
+            result.insert(key.to_string(), val.to_string());
         }
     }
     result
@@ -36,18 +34,25 @@ pub fn profile_for(email: &str) -> String {
 }
 
 pub fn encrypt_kv(input: &str, key: impl AsRef<[u8]>) -> Vec<u8> {
-    let padded = pad_to_block_size(input.bytes().collect::<Vec<_>>().as_slice(), AES_BLOCK_SIZE);
+    let padded = pad_to_block_size(input.bytes().collect::<Vec<_>>().as_slice(), AES_BLOCK_SIZE)
+        .expect("AES block size is valid for PKCS#7");
     encrypt_aes_ecb(padded.as_slice(), key.as_ref())
 }
 
-pub fn decrypt_kv<T: AsRef<[u8]>>(cipher: T, key: T) -> String {
-    decrypt_aes_ecb(cipher, key)
+/// Tampered ciphertext with broken padding is reported as an error.
+pub fn decrypt_kv<T: AsRef<[u8]>>(cipher: T, key: T) -> Result<String, DecryptError> {
+    Ok(decrypt_aes_ecb(cipher, key)?
         .iter()
         .map(|&v| v as char)
-        .collect::<String>()
+        .collect::<String>())
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::unnecessary_to_owned,
+    clippy::manual_memcpy,
+    clippy::useless_vec
+)]
 mod tests {
     use crate::prelude::random_bytes;
 
@@ -90,7 +95,7 @@ mod tests {
             paste_encrypted[i] = cut_block[i - 32];
         }
 
-        let decrypted = decrypt_kv(&paste_encrypted, &known_key);
+        let decrypted = decrypt_kv(&paste_encrypted, &known_key).unwrap();
         let vals = parse_kv_sequence(decrypted.as_str());
         println!("{:?}", decrypted);
         assert_eq!(vals.len(), 3);
@@ -101,4 +106,19 @@ mod tests {
         assert_eq!(vals.get(&"uid".to_string()), Some(&"10".to_string()));
         assert_eq!(vals.get(&"role".to_string()), Some(&"admin".to_string()));
     }
+
+    #[test]
+    fn decrypt_kv_tampered_should_pass() {
+        let key = random_bytes(AES_BLOCK_SIZE);
+        let mut encrypted = encrypt_kv(&profile_for("foo@bar.com"), &key);
+        let last = encrypted.len() - AES_BLOCK_SIZE;
+        // last block decrypts to zeros, which is never valid padding
+        encrypted[last..].copy_from_slice(&encrypt_aes_ecb(&[0; AES_BLOCK_SIZE][..], &key));
+        assert!(matches!(
+            decrypt_kv(&encrypted, &key),
+            Err(DecryptError::Padding(_))
+        ));
+        encrypted.pop();
+        assert!(decrypt_kv(&encrypted, &key).is_err());
+    }
 }